@@ -1,94 +1,1107 @@
-use std::fs::read_to_string;
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BinaryHeap, HashMap, VecDeque},
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::PathBuf,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
+use serde::Serialize;
+
+// Lists holds the location lists side by side, one column per list, every line of the input has a value for each
+#[derive(Serialize)]
 struct Lists {
-    list_a: Vec<i32>,
-    list_b: Vec<i32>,
+    columns: Vec<Vec<i32>>,
+}
+
+// Metric is how far apart two paired values are, selected with --metric absolute|squared|relative|capped:<cap>
+#[derive(Clone, Copy, Debug)]
+enum Metric {
+    Absolute,
+    Squared,
+    // the difference relative to the larger of the two values
+    Relative,
+    // the absolute difference, but never more than the cap
+    Capped(i64),
+}
+
+impl Metric {
+    fn between(&self, a: i32, b: i32) -> Distance {
+        let difference = (a as i128 - b as i128).abs();
+        match self {
+            Metric::Absolute => Distance::Exact(difference),
+            Metric::Squared => Distance::Exact(difference * difference),
+            Metric::Relative => {
+                let larger = (a as f64).abs().max((b as f64).abs());
+                Distance::Ratio(if larger == 0.0 { 0.0 } else { difference as f64 / larger })
+            }
+            Metric::Capped(cap) => Distance::Exact(difference.min(*cap as i128)),
+        }
+    }
+}
+
+// Distance is what a metric measures, a whole number for every metric but the relative one,
+// added up as integers so the sum of billions of pairs stays exact
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+enum Distance {
+    Exact(i128),
+    Ratio(f64),
+}
+
+impl Distance {
+    const ZERO: Distance = Distance::Exact(0);
+
+    fn as_f64(self) -> f64 {
+        match self {
+            Distance::Exact(value) => value as f64,
+            Distance::Ratio(value) => value,
+        }
+    }
+
+    // times is the distance of that many pairs at the same distance
+    fn times(self, count: usize) -> Distance {
+        match self {
+            Distance::Exact(value) => Distance::Exact(value * count as i128),
+            Distance::Ratio(value) => Distance::Ratio(value * count as f64),
+        }
+    }
+
+    // rounded keeps whole numbers as they are and ratios to three decimals
+    fn rounded(&self) -> String {
+        match self {
+            Distance::Exact(value) => value.to_string(),
+            Distance::Ratio(value) => round(*value),
+        }
+    }
+}
+
+impl std::ops::Add for Distance {
+    type Output = Distance;
+
+    fn add(self, other: Distance) -> Distance {
+        match (self, other) {
+            (Distance::Exact(a), Distance::Exact(b)) => Distance::Exact(a + b),
+            (a, b) => Distance::Ratio(a.as_f64() + b.as_f64()),
+        }
+    }
+}
+
+impl std::ops::AddAssign for Distance {
+    fn add_assign(&mut self, other: Distance) {
+        *self = *self + other;
+    }
+}
+
+impl std::iter::Sum for Distance {
+    fn sum<I: Iterator<Item = Distance>>(iter: I) -> Distance {
+        iter.fold(Distance::ZERO, |sum, distance| sum + distance)
+    }
+}
+
+impl std::fmt::Display for Distance {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Distance::Exact(value) => write!(f, "{}", value),
+            Distance::Ratio(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.split_once(':') {
+            None if input == "absolute" => Ok(Metric::Absolute),
+            None if input == "squared" => Ok(Metric::Squared),
+            None if input == "relative" => Ok(Metric::Relative),
+            Some(("capped", cap)) => cap.parse().map(Metric::Capped).map_err(|_| format!("`{}` is not a cap, caps are whole numbers", cap)),
+            _ => Err(format!("unknown metric `{}`, expected absolute, squared, relative or capped:<cap>", input)),
+        }
+    }
+}
+
+// Summary describes the paired distances of the first two lists, printed and dumped with --summary
+#[derive(Serialize)]
+struct Summary {
+    count: usize,
+    min: f64,
+    max: f64,
+    mean: f64,
+    median: f64,
+    percentiles: Vec<(u32, f64)>,
+    histogram: Vec<Bucket>,
+}
+
+// Bucket counts the distances from its start up to its end, the last bucket includes its end
+#[derive(Serialize)]
+struct Bucket {
+    start: f64,
+    end: f64,
+    count: usize,
+}
+
+const PERCENTILES: [u32; 5] = [10, 25, 75, 90, 99];
+const BUCKETS: usize = 10;
+
+impl Summary {
+    fn new(distances: &[f64]) -> Option<Self> {
+        if distances.is_empty() {
+            return None;
+        }
+        let mut sorted = distances.to_vec();
+        sorted.sort_unstable_by(|a, b| a.total_cmp(b));
+        let count = sorted.len();
+        let (min, max) = (sorted[0], sorted[count - 1]);
+        let median = if count.is_multiple_of(2) { (sorted[count / 2 - 1] + sorted[count / 2]) / 2.0 } else { sorted[count / 2] };
+        // nearest rank, the smallest distance with at least p% of the distances at or below it
+        let percentiles = PERCENTILES.iter()
+            .map(|&p| (p, sorted[((p as f64 / 100.0 * count as f64).ceil() as usize).max(1) - 1]))
+            .collect();
+        let buckets = if max > min { BUCKETS } else { 1 };
+        let width = (max - min) / buckets as f64;
+        let mut histogram: Vec<Bucket> = (0..buckets)
+            .map(|idx| Bucket { start: min + width * idx as f64, end: if idx + 1 == buckets { max } else { min + width * (idx + 1) as f64 }, count: 0 })
+            .collect();
+        for distance in &sorted {
+            let idx = if width > 0.0 { (((distance - min) / width) as usize).min(buckets - 1) } else { 0 };
+            histogram[idx].count += 1;
+        }
+        Some(Summary { count, min, max, mean: sorted.iter().sum::<f64>() / count as f64, median, percentiles, histogram })
+    }
+
+    fn print(&self) {
+        println!("Pairs: {}", self.count);
+        println!("Min: {}", round(self.min));
+        println!("Max: {}", round(self.max));
+        println!("Mean: {}", round(self.mean));
+        println!("Median: {}", round(self.median));
+        for (p, value) in &self.percentiles {
+            println!("P{}: {}", p, round(*value));
+        }
+        let largest = self.histogram.iter().map(|bucket| bucket.count).max().unwrap_or(0).max(1);
+        for bucket in &self.histogram {
+            let bar = "#".repeat((bucket.count * 40).div_ceil(largest));
+            println!("{:>10} - {:<10} {:>8} {}", round(bucket.start), round(bucket.end), bucket.count, bar);
+        }
+        println!("----------------");
+    }
+}
+
+// round keeps whole numbers as they are and the others to three decimals
+fn round(value: f64) -> String {
+    if value.fract() == 0.0 { format!("{}", value) } else { format!("{:.3}", value) }
+}
+
+// State is what --dump-state writes, the lists with the distance and similarity between the first two,
+// and between every pair of lists
+#[derive(Serialize)]
+struct State<'a> {
+    lists: &'a Lists,
+    metric: String,
+    distance: Option<Distance>,
+    similarity: Option<i64>,
+    summary: Option<Summary>,
+    distances: Option<Vec<Vec<Distance>>>,
+    similarities: Option<Vec<Vec<i64>>>,
 }
 
 fn main() {
     let input = std::env::args().nth(1).expect("Please provide an input");
-    let lists = parse_input(&input);
+    // --part 1 only computes the distance, --part 2 only the similarity, both are computed without it
+    let part = std::env::args().skip_while(|arg| arg != "--part").nth(1)
+        .map(|part| match part.as_str() {
+            "1" => 1,
+            "2" => 2,
+            _ => panic!("Part must be 1 or 2, found `{}`", part),
+        });
+    let metric: Metric = std::env::args().skip_while(|arg| arg != "--metric").nth(1)
+        .map_or(Metric::Absolute, |metric| metric.parse().expect("Invalid metric"));
 
-    for (a, b) in lists.list_a.iter().zip(lists.list_b.iter()) {
-        println!("{} {}", a, b);
+    let policy: Mismatch = std::env::args().skip_while(|arg| arg != "--on-mismatch").nth(1)
+        .map_or(Mismatch::Error, |policy| policy.parse().expect("Invalid mismatch policy"));
+
+    if std::env::args().any(|arg| arg == "--external") {
+        let run_size = std::env::args().skip_while(|arg| arg != "--run-size").nth(1)
+            .map_or(RUN_SIZE, |size| size.parse().expect("Run size must be a number of values"));
+        external(&input, part, metric, run_size, policy);
+        return;
+    }
+
+    let lists = parse_input(&input, policy);
+
+    for row in 0..lists.columns[0].len() {
+        let values: Vec<String> = lists.columns.iter().map(|column| column[row].to_string()).collect();
+        println!("{}", values.join(" "));
     }
     print!("----------------\n");
 
-    let mut sorted_a = sort_asc(&lists.list_a);
-    let mut sorted_b = sort_asc(&lists.list_b);
+    let paired = if part != Some(2) { Some(distance(&lists.columns[0], &lists.columns[1], metric)) } else { None };
+    let distance = paired.as_ref().map(|paired| paired.iter().copied().sum());
+    let summary = match &paired {
+        Some(paired) if std::env::args().any(|arg| arg == "--summary") => {
+            Summary::new(&paired.iter().map(|distance| distance.as_f64()).collect::<Vec<f64>>())
+        }
+        _ => None,
+    };
+    if let Some(summary) = &summary {
+        summary.print();
+    }
+    let similarity = if part != Some(1) { Some(similarity(&lists.columns[0], &lists.columns[1])) } else { None };
+
+    // with more than two lists, every pair of them is compared as well
+    let mut distances = None;
+    let mut similarities = None;
+    if lists.columns.len() > 2 {
+        if part != Some(2) {
+            let matrix = distance_matrix(&lists, metric);
+            print_matrix("Distances", &matrix, |value| value.rounded());
+            distances = Some(matrix);
+        }
+        if part != Some(1) {
+            let matrix = similarity_matrix(&lists);
+            print_matrix("Similarities", &matrix, |value| value.to_string());
+            similarities = Some(matrix);
+        }
+    }
+
+    if let Some(path) = std::env::args().skip_while(|arg| arg != "--dump-state").nth(1) {
+        let state = State { lists: &lists, metric: format!("{:?}", metric), distance, similarity, summary, distances, similarities };
+        serde_json::to_writer_pretty(File::create(&path).expect("Failed to create state file"), &state)
+            .expect("Failed to write state");
+        println!("State dumped to {}", path);
+    }
+
+    if let Some(path) = std::env::args().skip_while(|arg| arg != "--edits").nth(1) {
+        apply_edits(&lists, &path, metric);
+    }
+}
+
+// distance pairs up the values of both lists in sorted order and adds the distances between them,
+// every paired distance is returned for the summary
+fn distance(list_a: &[i32], list_b: &[i32], metric: Metric) -> Vec<Distance> {
+    let sorted_a = sort_asc(list_a);
+    let sorted_b = sort_asc(list_b);
 
     for (a, b) in sorted_a.iter().zip(sorted_b.iter()) {
         println!("{} {}", a, b);
     }
     print!("----------------\n");
 
-    let mut distances = Vec::new();
+    let paired: Vec<Distance> = distances(&sorted_a, &sorted_b, metric).collect();
+    let mut sum = Distance::ZERO;
+    for distance in &paired {
+        sum += *distance;
+        println!("{}", distance);
+    }
+
+    println!("Sum: {}\n", sum);
+    print!("----------------\n");
+    paired
+}
 
-    while !sorted_a.is_empty() && !sorted_b.is_empty() {
-        let a = sorted_a.pop().unwrap();
-        let b = sorted_b.pop().unwrap();
+// distances of two sorted lists, from their largest values down
+fn distances<'a>(sorted_a: &'a [i32], sorted_b: &'a [i32], metric: Metric) -> impl Iterator<Item = Distance> + 'a {
+    sorted_a.iter().rev().zip(sorted_b.iter().rev()).map(move |(a, b)| metric.between(*a, *b))
+}
 
-        // check if the difference is negative
-        // if it is, we need to add the absolute value
-        // of the difference to the distances list
+// similarity adds every item in list a multiplied by how many times it appears in list b,
+// the occurrences are counted once in a frequency map instead of scanning list b for every item
+fn similarity(list_a: &[i32], list_b: &[i32]) -> i64 {
+    let counts = frequencies(list_b);
 
-        if (a - b) < 0 {
-            distances.push(b - a);
-        } else {
-            distances.push(a - b);
+    let mut similarity = 0;
+    for score in scores(list_a, &counts) {
+        println!("{}", score);
+        similarity += score;
+    }
+    print!("Similarity: {}\n", similarity);
+    similarity
+}
+
+fn frequencies(list: &[i32]) -> HashMap<i32, i64> {
+    let mut counts = HashMap::new();
+    for item in list {
+        *counts.entry(*item).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn scores<'a>(list: &'a [i32], counts: &'a HashMap<i32, i64>) -> impl Iterator<Item = i64> + 'a {
+    list.iter().map(|item| counts.get(item).unwrap_or(&0) * *item as i64)
+}
+
+// distance_matrix holds the distance between every pair of lists, each list is only sorted once
+fn distance_matrix(lists: &Lists, metric: Metric) -> Vec<Vec<Distance>> {
+    let sorted: Vec<Vec<i32>> = lists.columns.iter().map(|column| sort_asc(column)).collect();
+    sorted.iter()
+        .map(|a| sorted.iter().map(|b| distances(a, b, metric).sum()).collect())
+        .collect()
+}
+
+// similarity_matrix holds the similarity of the list in each row to the list in each column,
+// it isn't symmetric as only the items of the row list are scored
+fn similarity_matrix(lists: &Lists) -> Vec<Vec<i64>> {
+    let counts: Vec<HashMap<i32, i64>> = lists.columns.iter().map(|column| frequencies(column)).collect();
+    lists.columns.iter()
+        .map(|a| counts.iter().map(|b| scores(a, b).sum()).collect())
+        .collect()
+}
+
+// print_matrix draws a matrix with the lists numbered from 1 along both sides
+fn print_matrix<T>(title: &str, matrix: &[Vec<T>], format: impl Fn(&T) -> String) {
+    let matrix: Vec<Vec<String>> = matrix.iter().map(|row| row.iter().map(&format).collect()).collect();
+    let width = matrix.iter().flatten().map(|value| value.len()).max().unwrap_or(1).max(matrix.len().to_string().len());
+    println!("{}:", title);
+    print!("{:>width$}", "", width = width);
+    for idx in 1..=matrix.len() {
+        print!(" {:>width$}", idx, width = width);
+    }
+    println!();
+    for (idx, row) in matrix.iter().enumerate() {
+        print!("{:>width$}", idx + 1, width = width);
+        for value in row {
+            print!(" {:>width$}", value, width = width);
         }
+        println!();
     }
-    
-    let mut sum = 0;
-    for distance in distances {
-        sum += distance;
-        println!("{}", distance);
+    println!("----------------");
+}
+
+// Reconciliation holds the first two lists in ordered multisets, so single location IDs can be added to
+// or removed from either of them in O(log n), and the similarity is updated along with every change in O(log n).
+// The distance isn't: one value moves the rank of every value above it, so each call to distance walks both
+// multisets in order, O(d) for d distinct values (O(n) at worst), without sorting or allocating anything
+struct Reconciliation {
+    list_a: BTreeMap<i32, usize>,
+    list_b: BTreeMap<i32, usize>,
+    similarity: i64,
+}
+
+impl Reconciliation {
+    fn new(list_a: &[i32], list_b: &[i32]) -> Self {
+        let mut reconciliation = Reconciliation { list_a: BTreeMap::new(), list_b: BTreeMap::new(), similarity: 0 };
+        for a in list_a {
+            reconciliation.insert_a(*a);
+        }
+        for b in list_b {
+            reconciliation.insert_b(*b);
+        }
+        reconciliation
     }
 
-    println!("Sum: {}\n", sum);
-    print!("----------------\n");
+    fn insert_a(&mut self, value: i32) {
+        *self.list_a.entry(value).or_insert(0) += 1;
+        self.similarity += value as i64 * *self.list_b.get(&value).unwrap_or(&0) as i64;
+    }
+
+    fn insert_b(&mut self, value: i32) {
+        *self.list_b.entry(value).or_insert(0) += 1;
+        self.similarity += value as i64 * *self.list_a.get(&value).unwrap_or(&0) as i64;
+    }
+
+    // remove_a takes one occurrence of value out of list a, false when there is none
+    fn remove_a(&mut self, value: i32) -> bool {
+        if !remove_one(&mut self.list_a, value) {
+            return false;
+        }
+        self.similarity -= value as i64 * *self.list_b.get(&value).unwrap_or(&0) as i64;
+        true
+    }
+
+    // remove_b takes one occurrence of value out of list b, false when there is none
+    fn remove_b(&mut self, value: i32) -> bool {
+        if !remove_one(&mut self.list_b, value) {
+            return false;
+        }
+        self.similarity -= value as i64 * *self.list_a.get(&value).unwrap_or(&0) as i64;
+        true
+    }
+
+    fn similarity(&self) -> i64 {
+        self.similarity
+    }
+
+    // distance pairs the values from the largest down like the sorted lists do,
+    // the smallest values of the longer list are left out. Equal values are paired a whole run at a time
+    fn distance(&self, metric: Metric) -> Distance {
+        let mut runs_a = self.list_a.iter().rev().map(|(value, count)| (*value, *count));
+        let mut runs_b = self.list_b.iter().rev().map(|(value, count)| (*value, *count));
+        let (mut run_a, mut run_b) = (runs_a.next(), runs_b.next());
+        let mut distance = Distance::ZERO;
+        while let (Some((a, count_a)), Some((b, count_b))) = (run_a, run_b) {
+            let paired = count_a.min(count_b);
+            distance += metric.between(a, b).times(paired);
+            run_a = if count_a > paired { Some((a, count_a - paired)) } else { runs_a.next() };
+            run_b = if count_b > paired { Some((b, count_b - paired)) } else { runs_b.next() };
+        }
+        distance
+    }
+}
+
+fn remove_one(list: &mut BTreeMap<i32, usize>, value: i32) -> bool {
+    match list.get_mut(&value) {
+        Some(count) if *count > 1 => *count -= 1,
+        Some(_) => {
+            list.remove(&value);
+        }
+        None => return false,
+    }
+    true
+}
+
+// apply_edits reads one change per line, `insert a 3` or `remove b 4`, and prints both answers after each of them
+fn apply_edits(lists: &Lists, filename: &str, metric: Metric) {
+    let mut reconciliation = Reconciliation::new(&lists.columns[0], &lists.columns[1]);
+    for (idx, line) in input::lines(filename).enumerate() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let [action, list, value] = words[..] else {
+            panic!("Edits look like `insert a 3`, found `{}` on line {}", line, idx + 1);
+        };
+        let value: i32 = value.parse().expect("Location IDs must be integers");
+        let applied = match (action, list) {
+            ("insert", "a") => { reconciliation.insert_a(value); true }
+            ("insert", "b") => { reconciliation.insert_b(value); true }
+            ("remove", "a") => reconciliation.remove_a(value),
+            ("remove", "b") => reconciliation.remove_b(value),
+            _ => panic!("Unknown edit `{}` on line {}", line, idx + 1),
+        };
+        if !applied {
+            println!("{}: {} is not in list {}", line, value, list);
+            continue;
+        }
+        println!("{}: distance {}, similarity {}", line, reconciliation.distance(metric), reconciliation.similarity());
+    }
+}
+
+// RUN_SIZE is how many values of a list are sorted in memory at once by --external, 4 MiB of them
+const RUN_SIZE: usize = 1 << 20;
+
+// external computes the distance and similarity of the first two lists without holding them in memory:
+// the input is split into sorted runs written to temporary files, then the runs of both lists are merged
+// side by side, once, and both measures are taken from the merged values as they come out
+fn external(filename: &str, part: Option<u8>, metric: Metric, run_size: usize, policy: Mismatch) {
+    let runs = split_runs(filename, run_size, policy);
+    let (distance, similarity) = merge_runs(&runs, metric);
+
+    if part != Some(2) {
+        println!("Sum: {}\n", distance);
+    }
+    if part != Some(1) {
+        println!("Similarity: {}", similarity);
+    }
+}
+
+// merge_runs merges the runs of both lists side by side and returns their distance and similarity
+fn merge_runs([runs_a, runs_b]: &[Runs; 2], metric: Metric) -> (Distance, i64) {
+    let mut sorted_a = Merge::new(runs_a);
+    let mut sorted_b = Merge::new(runs_b);
+    let mut groups_a = Groups::default();
+    let mut groups_b = Groups::default();
+    let mut distance = Distance::ZERO;
+    let mut similarity: i64 = 0;
+    while let (Some(a), Some(b)) = (sorted_a.next(), sorted_b.next()) {
+        distance += metric.between(a, b);
+        groups_a.push(a);
+        groups_b.push(b);
+        similarity += join(&mut groups_a, &mut groups_b);
+    }
+    groups_a.finish();
+    groups_b.finish();
+    similarity += join(&mut groups_a, &mut groups_b);
+    (distance, similarity)
+}
+
+// Runs are the sorted chunks of one list, in temporary files of little endian values removed once merged
+struct Runs {
+    paths: Vec<PathBuf>,
+}
+
+impl Drop for Runs {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
 
-    // similarity scores
-    let mut scores = Vec::new();
+// split_runs streams the input through a LineCheck and writes a sorted run of the first two lists every
+// run_size values, the other lists are checked but not kept. The mismatch policy is applied as parse_input does:
+// truncating needs the shortest length up front, so the input is first read once to count the values,
+// and padding adds the missing values to the last runs. Duplicates aren't looked for, it would take every value
+fn split_runs(filename: &str, run_size: usize, policy: Mismatch) -> [Runs; 2] {
+    let limit = match policy {
+        Mismatch::Truncate => {
+            let mut count = LineCheck::new();
+            for (idx, line) in input::lines(filename).enumerate() {
+                count.values(idx, &line);
+            }
+            count.finish().0
+        }
+        _ => usize::MAX,
+    };
 
-    // similarity is calculated by how many times an item in list a appears in list b), multiplied by the item itself
-    for a in lists.list_a.iter() {
-        let mut count = 0;
-        for b in lists.list_b.iter() {
-            if a == b {
-                count += 1;
+    let mut runs = [Runs { paths: Vec::new() }, Runs { paths: Vec::new() }];
+    let mut chunks = [Vec::with_capacity(run_size), Vec::with_capacity(run_size)];
+    let mut kept = [0; 2];
+    let mut check = LineCheck::new();
+    for (idx, line) in input::lines(filename).enumerate() {
+        let values = check.values(idx, &line);
+        for (list, chunk) in chunks.iter_mut().enumerate() {
+            let Some(value) = values.get(list).copied().flatten() else { continue };
+            if kept[list] == limit {
+                continue;
+            }
+            kept[list] += 1;
+            chunk.push(value);
+            if chunk.len() == run_size {
+                write_run(chunk, list, &mut runs[list]);
             }
         }
-        scores.push(count * a);
     }
+    let (_, longest) = check.finish();
+    if check.stops(policy) {
+        // the runs are removed when dropped, which exiting in report would skip
+        drop(runs);
+        check.report(filename, policy);
+        unreachable!("report stops on errors");
+    }
+    if let Mismatch::Pad(value) = policy {
+        for (list, chunk) in chunks.iter_mut().enumerate() {
+            for _ in kept[list]..longest {
+                chunk.push(value);
+                if chunk.len() == run_size {
+                    write_run(chunk, list, &mut runs[list]);
+                }
+            }
+        }
+    }
+    check.report(filename, policy);
+
+    for (list, chunk) in chunks.iter_mut().enumerate() {
+        if !chunk.is_empty() {
+            write_run(chunk, list, &mut runs[list]);
+        }
+    }
+    runs
+}
+
+// RUNS_WRITTEN numbers the run files of the process, so runs split at the same time never share a file
+static RUNS_WRITTEN: AtomicUsize = AtomicUsize::new(0);
+
+fn write_run(chunk: &mut Vec<i32>, list: usize, runs: &mut Runs) {
+    chunk.sort_unstable();
+    let run = RUNS_WRITTEN.fetch_add(1, Ordering::Relaxed);
+    let path = std::env::temp_dir().join(format!("day-1-{}-{}-{}.run", std::process::id(), list, run));
+    let mut writer = BufWriter::new(File::create(&path).expect("Failed to create run file"));
+    for value in chunk.iter() {
+        writer.write_all(&value.to_le_bytes()).expect("Failed to write run file");
+    }
+    writer.flush().expect("Failed to write run file");
+    runs.paths.push(path);
+    chunk.clear();
+}
+
+// Merge yields the values of every run of a list in ascending order, holding one value per run
+struct Merge {
+    readers: Vec<BufReader<File>>,
+    heap: BinaryHeap<Reverse<(i32, usize)>>,
+}
+
+impl Merge {
+    fn new(runs: &Runs) -> Self {
+        let mut readers: Vec<BufReader<File>> = runs.paths.iter()
+            .map(|path| BufReader::new(File::open(path).expect("Failed to open run file")))
+            .collect();
+        let heap = readers.iter_mut().enumerate()
+            .filter_map(|(run, reader)| read_value(reader).map(|value| Reverse((value, run))))
+            .collect();
+        Merge { readers, heap }
+    }
+}
+
+impl Iterator for Merge {
+    type Item = i32;
+
+    fn next(&mut self) -> Option<i32> {
+        let Reverse((value, run)) = self.heap.pop()?;
+        if let Some(next) = read_value(&mut self.readers[run]) {
+            self.heap.push(Reverse((next, run)));
+        }
+        Some(value)
+    }
+}
+
+fn read_value(reader: &mut BufReader<File>) -> Option<i32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes).ok()?;
+    Some(i32::from_le_bytes(bytes))
+}
+
+// Groups counts the runs of equal values of a sorted list, the finished ones wait in line until
+// the other list has caught up with them, so only the groups one list is ahead of the other are kept
+#[derive(Default)]
+struct Groups {
+    current: Option<(i32, i64)>,
+    finished: VecDeque<(i32, i64)>,
+}
+
+impl Groups {
+    fn push(&mut self, value: i32) {
+        match &mut self.current {
+            Some((current, count)) if *current == value => *count += 1,
+            _ => {
+                self.finish();
+                self.current = Some((value, 1));
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        if let Some(group) = self.current.take() {
+            self.finished.push_back(group);
+        }
+    }
+}
 
+// join matches the finished groups of both lists and returns the similarity of the values found in both
+fn join(groups_a: &mut Groups, groups_b: &mut Groups) -> i64 {
     let mut similarity = 0;
-    for score in scores {
-        println!("{}", score);
-        similarity += score;
+    while let (Some(&(a, count_a)), Some(&(b, count_b))) = (groups_a.finished.front(), groups_b.finished.front()) {
+        if a <= b {
+            groups_a.finished.pop_front();
+        }
+        if b <= a {
+            groups_b.finished.pop_front();
+        }
+        if a == b {
+            similarity += a as i64 * count_a * count_b;
+        }
     }
-    print!("Similarity: {}\n", similarity);
+    similarity
+}
 
+// Mismatch is how --on-mismatch goes on when the lists don't have as many values each:
+// error stops after reporting the problems, pad fills the shorter lists with a value (0 unless given as pad:<value>)
+// and truncate cuts every list to the length of the shortest one
+#[derive(Clone, Copy)]
+enum Mismatch {
+    Error,
+    Pad(i32),
+    Truncate,
 }
 
-fn parse_input(filename: &str) -> Lists {
+impl FromStr for Mismatch {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.split_once(':') {
+            None if input == "error" => Ok(Mismatch::Error),
+            None if input == "pad" => Ok(Mismatch::Pad(0)),
+            None if input == "truncate" => Ok(Mismatch::Truncate),
+            Some(("pad", value)) => value.parse().map(Mismatch::Pad).map_err(|_| format!("`{}` is not a location ID", value)),
+            _ => Err(format!("unknown policy `{}`, expected error, pad, pad:<value> or truncate", input)),
+        }
+    }
+}
+
+// Diagnostic is a problem found while reading the lists, line 0 is about the file as a whole.
+// Warnings are reported but never stop the solver
+struct Diagnostic {
+    line: usize,
+    message: String,
+    warning: bool,
+}
+
+impl Diagnostic {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Diagnostic { line, message: message.into(), warning: false }
+    }
+
+    fn warning(line: usize, message: impl Into<String>) -> Self {
+        Diagnostic { line, message: message.into(), warning: true }
+    }
+}
+
+// DUPLICATE_SHARE is the share of values repeating an earlier one above which a list is reported as duplicate heavy
+const DUPLICATE_SHARE: f64 = 0.5;
+
+// LineCheck reads the values of the lists line by line, as many lists as values on the first line (at least two),
+// and keeps a diagnostic for every blank line, value that isn't an integer or line with a different number of values
+struct LineCheck {
+    columns: usize,
+    lengths: Vec<usize>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl LineCheck {
+    fn new() -> Self {
+        LineCheck { columns: 0, lengths: Vec::new(), diagnostics: Vec::new() }
+    }
+
+    // values returns the value of every list on the line, None for the lists it has no value for,
+    // and nothing at all for a blank line
+    fn values(&mut self, idx: usize, line: &str) -> Vec<Option<i32>> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if tokens.is_empty() {
+            self.diagnostics.push(Diagnostic::new(idx + 1, "blank line"));
+            return Vec::new();
+        }
+        if self.columns == 0 {
+            self.columns = tokens.len().max(2);
+            self.lengths = vec![0; self.columns];
+            if tokens.len() < 2 {
+                self.diagnostics.push(Diagnostic::new(idx + 1, format!("expected at least 2 values, found {}", tokens.len())));
+            }
+        } else if tokens.len() != self.columns {
+            self.diagnostics.push(Diagnostic::new(idx + 1, format!("expected {} values, found {}", self.columns, tokens.len())));
+        }
+        // a value that can't be read is missing from its list, the lists are sorted so rows don't have to line up
+        let mut values = vec![None; self.columns];
+        for (column, token) in tokens.into_iter().take(self.columns).enumerate() {
+            match token.parse() {
+                Ok(value) => {
+                    values[column] = Some(value);
+                    self.lengths[column] += 1;
+                }
+                Err(_) => self.diagnostics.push(Diagnostic::new(idx + 1, format!("`{}` is not an integer", token))),
+            }
+        }
+        values
+    }
+
+    // finish reports the lists left with unequal lengths, and returns the shortest and longest lengths
+    fn finish(&mut self) -> (usize, usize) {
+        let longest = self.lengths.iter().copied().max().unwrap_or(0);
+        let shortest = self.lengths.iter().copied().min().unwrap_or(0);
+        for (idx, &length) in self.lengths.iter().enumerate() {
+            if length != longest {
+                self.diagnostics.push(Diagnostic::new(0, format!("list {} has {} values, the longest has {}", idx + 1, length, longest)));
+            }
+        }
+        if self.columns == 0 {
+            self.diagnostics.push(Diagnostic::new(0, "no lists in the file"));
+        }
+        (shortest, longest)
+    }
+
+    // stops tells if the solver can't go on, when the policy is to stop on errors or there is nothing to go on with
+    fn stops(&self, policy: Mismatch) -> bool {
+        self.columns == 0 || (matches!(policy, Mismatch::Error) && self.diagnostics.iter().any(|diagnostic| !diagnostic.warning))
+    }
+
+    // report prints the diagnostics on stderr and exits when the solver stops
+    fn report(&self, filename: &str, policy: Mismatch) {
+        for diagnostic in &self.diagnostics {
+            let kind = if diagnostic.warning { "warning" } else { "error" };
+            if diagnostic.line == 0 {
+                eprintln!("{}: {}: {}", filename, kind, diagnostic.message);
+            } else {
+                eprintln!("{}:{}: {}: {}", filename, diagnostic.line, kind, diagnostic.message);
+            }
+        }
+        if self.stops(policy) {
+            if self.columns != 0 {
+                eprintln!("{}: stopping, use --on-mismatch pad or truncate to go on", filename);
+            }
+            std::process::exit(1);
+        }
+    }
+}
+
+// parse_input reads the lists through a LineCheck, its diagnostics are reported on stderr with their line numbers
+// along with the lists made mostly of duplicates; the mismatch policy decides how to go on
+fn parse_input(filename: &str, policy: Mismatch) -> Lists {
+    let (lists, check) = read_lists(filename, policy);
+    check.report(filename, policy);
+    lists
+}
+
+// read_lists reads the lists and applies the mismatch policy to them, the check holds what was found on the way
+fn read_lists(filename: &str, policy: Mismatch) -> (Lists, LineCheck) {
     let mut output = Lists {
-        list_a: Vec::new(),
-        list_b: Vec::new(),
+        columns: Vec::new(),
     };
+    let mut check = LineCheck::new();
+
+    for (idx, line) in input::lines(filename).enumerate() {
+        let values = check.values(idx, &line);
+        if output.columns.len() < values.len() {
+            output.columns.resize(values.len(), Vec::new());
+        }
+        for (column, value) in output.columns.iter_mut().zip(values) {
+            column.extend(value);
+        }
+    }
+    let (shortest, longest) = check.finish();
+
+    for (idx, column) in output.columns.iter().enumerate() {
+        let counts = frequencies(column);
+        let repeated = column.len() - counts.len();
+        if !column.is_empty() && repeated as f64 / column.len() as f64 > DUPLICATE_SHARE {
+            let (value, count) = counts.iter().max_by_key(|(value, count)| (**count, -**value)).unwrap();
+            check.diagnostics.push(Diagnostic::warning(0, format!(
+                "list {} is duplicate heavy, {} of its {} values repeat an earlier one ({} appears {} times)",
+                idx + 1, repeated, column.len(), value, count)));
+        }
+    }
 
-    for line in read_to_string(filename).unwrap().lines() {
-        let mut iter = line.split_whitespace();
-        output.list_a.push(iter.next().unwrap().parse().unwrap());
-        output.list_b.push(iter.next().unwrap().parse().unwrap());
+    match policy {
+        Mismatch::Pad(value) => output.columns.iter_mut().for_each(|column| column.resize(longest, value)),
+        Mismatch::Truncate => output.columns.iter_mut().for_each(|column| column.truncate(shortest)),
+        Mismatch::Error => {}
     }
 
-    output
+    (output, check)
 }
 
 // sort_asc sorts a list of integers in ascending order
-fn sort_asc(list: &Vec<i32>) -> Vec<i32> {
-    let mut sorted = list.clone();
+fn sort_asc(list: &[i32]) -> Vec<i32> {
+    let mut sorted = list.to_vec();
     sorted.sort_unstable();
     sorted
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // TempFile is an input file written for a test, removed when dropped
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, contents: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("day-1-test-{}-{}.txt", std::process::id(), name));
+            std::fs::write(&path, contents).unwrap();
+            TempFile(path)
+        }
+
+        fn path(&self) -> &str {
+            self.0.to_str().unwrap()
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn read(name: &str, contents: &str, policy: Mismatch) -> (Vec<Vec<i32>>, LineCheck) {
+        let file = TempFile::new(name, contents);
+        let (lists, check) = read_lists(file.path(), policy);
+        (lists.columns, check)
+    }
+
+    // diagnostics lists the line, message and whether it is a warning of every diagnostic
+    fn diagnostics(check: &LineCheck) -> Vec<(usize, &str, bool)> {
+        check.diagnostics.iter().map(|diagnostic| (diagnostic.line, diagnostic.message.as_str(), diagnostic.warning)).collect()
+    }
+
+    #[test]
+    fn reports_blank_lines() {
+        let (lists, check) = read("blank", "3 4\n\n4 3\n", Mismatch::Error);
+        assert_eq!(diagnostics(&check), vec![(2, "blank line", false)]);
+        assert!(check.stops(Mismatch::Error));
+        assert!(!check.stops(Mismatch::Pad(0)));
+        assert_eq!(lists, vec![vec![3, 4], vec![4, 3]]);
+    }
+
+    #[test]
+    fn reports_values_that_are_not_integers() {
+        let (_, check) = read("token", "3 4\n4 x\n5 6\n", Mismatch::Error);
+        assert_eq!(diagnostics(&check), vec![
+            (2, "`x` is not an integer", false),
+            (0, "list 2 has 2 values, the longest has 3", false),
+        ]);
+        assert!(check.stops(Mismatch::Error));
+    }
+
+    #[test]
+    fn reports_a_short_first_line() {
+        let (_, check) = read("short", "3\n4 3\n", Mismatch::Error);
+        assert_eq!(diagnostics(&check), vec![
+            (1, "expected at least 2 values, found 1", false),
+            (0, "list 2 has 1 values, the longest has 2", false),
+        ]);
+        assert!(check.stops(Mismatch::Error));
+    }
+
+    #[test]
+    fn reports_lines_and_lists_of_unequal_lengths() {
+        let (lists, check) = read("unequal", "3 4\n4 3 5\n2\n", Mismatch::Error);
+        assert_eq!(diagnostics(&check), vec![
+            (2, "expected 2 values, found 3", false),
+            (3, "expected 2 values, found 1", false),
+            (0, "list 2 has 2 values, the longest has 3", false),
+        ]);
+        assert!(check.stops(Mismatch::Error));
+        assert_eq!(lists, vec![vec![3, 4, 2], vec![4, 3]]);
+    }
+
+    #[test]
+    fn warns_about_duplicate_heavy_lists_without_stopping() {
+        let (_, check) = read("duplicates", "1 1\n1 2\n1 3\n", Mismatch::Error);
+        assert_eq!(diagnostics(&check), vec![
+            (0, "list 1 is duplicate heavy, 2 of its 3 values repeat an earlier one (1 appears 3 times)", true),
+        ]);
+        assert!(!check.stops(Mismatch::Error));
+    }
+
+    #[test]
+    fn stops_on_a_file_without_lists_whatever_the_policy() {
+        let (_, check) = read("empty", "", Mismatch::Pad(0));
+        assert_eq!(diagnostics(&check), vec![(0, "no lists in the file", false)]);
+        for policy in [Mismatch::Error, Mismatch::Pad(0), Mismatch::Truncate] {
+            assert!(check.stops(policy));
+        }
+    }
+
+    #[test]
+    fn pads_or_truncates_the_shorter_lists() {
+        let contents = "3 4\n4 x\n5\n";
+        let (lists, check) = read("pad", contents, Mismatch::Pad(9));
+        assert!(!check.stops(Mismatch::Pad(9)));
+        assert_eq!(lists, vec![vec![3, 4, 5], vec![4, 9, 9]]);
+        let (lists, check) = read("truncate", contents, Mismatch::Truncate);
+        assert!(!check.stops(Mismatch::Truncate));
+        assert_eq!(lists, vec![vec![3], vec![4]]);
+        let (lists, _) = read("error", contents, Mismatch::Error);
+        assert_eq!(lists, vec![vec![3, 4, 5], vec![4]]);
+    }
+
+    // in_memory measures the first two lists the way main does without --external
+    fn in_memory(lists: &[Vec<i32>], metric: Metric) -> (Distance, i64) {
+        let distance = distances(&sort_asc(&lists[0]), &sort_asc(&lists[1]), metric).sum();
+        let similarity = scores(&lists[0], &frequencies(&lists[1])).sum();
+        (distance, similarity)
+    }
+
+    // random_lines makes lines of two values from a small range, so the lists are full of duplicates
+    fn random_lines(count: usize, seed: u64) -> String {
+        let mut state = seed;
+        let mut next = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 33) as i32 % 20 - 5
+        };
+        (0..count).map(|_| format!("{} {}\n", next(), next())).collect()
+    }
+
+    #[test]
+    fn external_merge_matches_the_in_memory_lists() {
+        for (name, contents) in [
+            ("example", "3   4\n4   3\n2   5\n1   3\n3   9\n3   3\n".to_string()),
+            ("random", random_lines(1000, 7)),
+            ("single", "5 5\n".to_string()),
+        ] {
+            let file = TempFile::new(&format!("external-{}", name), &contents);
+            let (lists, _) = read_lists(file.path(), Mismatch::Error);
+            for run_size in [1, 2, 3, 64, RUN_SIZE] {
+                let runs = split_runs(file.path(), run_size, Mismatch::Error);
+                for metric in [Metric::Absolute, Metric::Squared, Metric::Capped(3), Metric::Relative] {
+                    let external = merge_runs(&runs, metric);
+                    let expected = in_memory(&lists.columns, metric);
+                    if let Metric::Relative = metric {
+                        assert!((external.0.as_f64() - expected.0.as_f64()).abs() < 1e-6, "{} with runs of {}", name, run_size);
+                        assert_eq!(external.1, expected.1, "{} with runs of {}", name, run_size);
+                    } else {
+                        assert_eq!(external, expected, "{} {:?} with runs of {}", name, metric, run_size);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn external_merge_pads_and_truncates_like_the_in_memory_lists() {
+        let contents = "3 4\n4 x\n5\n3 3\n7\n3\n";
+        let file = TempFile::new("external-mismatch", contents);
+        for policy in [Mismatch::Pad(0), Mismatch::Pad(3), Mismatch::Truncate] {
+            let (lists, _) = read_lists(file.path(), policy);
+            for run_size in [1, 2, 4] {
+                let runs = split_runs(file.path(), run_size, policy);
+                assert_eq!(merge_runs(&runs, Metric::Absolute), in_memory(&lists.columns, Metric::Absolute));
+            }
+        }
+    }
+
+    #[test]
+    fn external_runs_are_removed_once_merged() {
+        let file = TempFile::new("external-cleanup", &random_lines(10, 3));
+        let runs = split_runs(file.path(), 2, Mismatch::Error);
+        let paths: Vec<PathBuf> = runs.iter().flat_map(|runs| runs.paths.clone()).collect();
+        assert_eq!(paths.len(), 10);
+        assert!(paths.iter().all(|path| path.exists()));
+        drop(runs);
+        assert!(paths.iter().all(|path| !path.exists()));
+    }
+
+    // check_reconciliation compares the answers kept up to date with the ones measured again from scratch
+    #[track_caller]
+    fn check_reconciliation(reconciliation: &Reconciliation, list_a: &[i32], list_b: &[i32]) {
+        let lists = [list_a.to_vec(), list_b.to_vec()];
+        for metric in [Metric::Absolute, Metric::Squared, Metric::Capped(4)] {
+            assert_eq!(reconciliation.distance(metric), in_memory(&lists, metric).0, "{:?} {:?} {:?}", metric, list_a, list_b);
+        }
+        assert_eq!(reconciliation.similarity(), in_memory(&lists, Metric::Absolute).1, "{:?} {:?}", list_a, list_b);
+    }
+
+    #[test]
+    fn reconciliation_follows_every_edit() {
+        let mut list_a = vec![3, 4, 2, 1, 3, 3];
+        let mut list_b = vec![4, 3, 5, 3, 9, 3];
+        let mut reconciliation = Reconciliation::new(&list_a, &list_b);
+        check_reconciliation(&reconciliation, &list_a, &list_b);
+        let mut state: u64 = 11;
+        for _ in 0..2000 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let value = (state >> 33) as i32 % 12 - 3;
+            match (state >> 20) % 4 {
+                0 => {
+                    reconciliation.insert_a(value);
+                    list_a.push(value);
+                }
+                1 => {
+                    reconciliation.insert_b(value);
+                    list_b.push(value);
+                }
+                2 => {
+                    let position = list_a.iter().position(|a| *a == value);
+                    assert_eq!(reconciliation.remove_a(value), position.is_some());
+                    if let Some(position) = position {
+                        list_a.swap_remove(position);
+                    }
+                }
+                _ => {
+                    let position = list_b.iter().position(|b| *b == value);
+                    assert_eq!(reconciliation.remove_b(value), position.is_some());
+                    if let Some(position) = position {
+                        list_b.swap_remove(position);
+                    }
+                }
+            }
+            check_reconciliation(&reconciliation, &list_a, &list_b);
+        }
+    }
+
+    #[test]
+    fn reconciliation_pairs_the_largest_values_of_lists_of_unequal_lengths() {
+        let mut reconciliation = Reconciliation::new(&[], &[]);
+        check_reconciliation(&reconciliation, &[], &[]);
+        reconciliation.insert_a(5);
+        check_reconciliation(&reconciliation, &[5], &[]);
+        reconciliation.insert_b(1);
+        reconciliation.insert_b(7);
+        reconciliation.insert_b(7);
+        check_reconciliation(&reconciliation, &[5], &[1, 7, 7]);
+        assert_eq!(reconciliation.distance(Metric::Absolute), Distance::Exact(2));
+        assert!(!reconciliation.remove_a(7));
+        assert!(reconciliation.remove_b(7));
+        assert!(reconciliation.remove_a(5));
+        check_reconciliation(&reconciliation, &[], &[1, 7]);
+        assert_eq!(reconciliation.similarity(), 0);
+    }
+}
@@ -1,143 +1,553 @@
-use std::fs::read_to_string;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufWriter, Write},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
 
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
 struct Report {
     levels: Vec<i32>,
 }
 
+// Safety is the verdict on a report, the levels the dampener removed to make it safe
+// or the first step breaking the rules when it can't be made safe
 enum Safety {
-    Safe,
-    Unsafe,
+    Safe { removed: Vec<Removed> },
+    Unsafe(Violation),
 }
 
-fn main() {
-    let input = std::env::args().nth(1).expect("Please provide an input");
-    let reports = parse_input(&input);
+#[derive(Clone, Serialize)]
+struct Removed {
+    index: usize,
+    level: i32,
+}
+
+// Violation is the first pair of levels, at index and index + 1, whose step breaks the rules
+#[derive(Clone, Serialize)]
+struct Violation {
+    index: usize,
+    delta: i32,
+    problem: Problem,
+}
 
-    let mut counter = 0;
-    for report in &reports {
-        for level in &report.levels {
-            print!("{} ", level);
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Problem {
+    Flat,
+    TooSmall,
+    TooLarge,
+    DirectionChange,
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Problem::Flat => write!(f, "flat step"),
+            Problem::TooSmall => write!(f, "too small a step"),
+            Problem::TooLarge => write!(f, "too large a step"),
+            Problem::DirectionChange => write!(f, "too many direction changes"),
         }
-        match check_safety(report.levels.clone()) {
-            Safety::Safe => {
-                print!("Safe");
-                counter += 1;
-            },
-            Safety::Unsafe => print!("Unsafe"),
+    }
+}
+
+impl std::fmt::Display for Safety {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Safety::Safe { removed } if removed.is_empty() => write!(f, "Safe"),
+            Safety::Safe { removed } => {
+                let removed: Vec<String> = removed.iter()
+                    .map(|removed| format!("{} at index {}", removed.level, removed.index))
+                    .collect();
+                write!(f, "Safe after removing {}", removed.join(", "))
+            }
+            Safety::Unsafe(violation) => write!(f, "Unsafe: {}", violation),
         }
-        print!("\n");
     }
-    print!("Safe reports: {}\n", counter);
-    println!("----------------");
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let direction = if self.delta > 0 { "up" } else { "down" };
+        write!(f, "levels at index {} and {} ", self.index, self.index + 1)?;
+        match self.problem {
+            Problem::Flat => write!(f, "stay flat"),
+            Problem::TooSmall => write!(f, "go {} by {}, too small a step", direction, self.delta.abs()),
+            Problem::TooLarge => write!(f, "go {} by {}, too large a step", direction, self.delta.abs()),
+            Problem::DirectionChange => write!(f, "go {} by {}, one direction change too many", direction, self.delta.abs()),
+        }
+    }
+}
+
+// SafetyRules describe what a safe report looks like for a reactor: every step between kept levels moves by
+// min_step to max_step, or stays flat when the rules aren't strict, and the levels may change direction
+// (up to down or down to up) at most direction_changes times. The defaults are the puzzle's rules.
+// They are read from a JSON file given with --rules, then from --min-step, --max-step, --non-strict
+// and --direction-changes
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+struct SafetyRules {
+    min_step: i32,
+    max_step: i32,
+    strict: bool,
+    direction_changes: usize,
+}
+
+impl Default for SafetyRules {
+    fn default() -> Self {
+        SafetyRules { min_step: 1, max_step: 3, strict: true, direction_changes: 0 }
+    }
+}
+
+impl SafetyRules {
+    fn from_args() -> Self {
+        let mut rules = match std::env::args().skip_while(|arg| arg != "--rules").nth(1) {
+            Some(path) => serde_json::from_reader(File::open(&path).expect("Failed to open rules"))
+                .expect("Failed to read rules"),
+            None => SafetyRules::default(),
+        };
+        let value = |flag: &str| std::env::args().skip_while(|arg| arg != flag).nth(1);
+        if let Some(step) = value("--min-step") {
+            rules.min_step = step.parse().expect("Min step must be a number");
+        }
+        if let Some(step) = value("--max-step") {
+            rules.max_step = step.parse().expect("Max step must be a number");
+        }
+        if std::env::args().any(|arg| arg == "--non-strict") {
+            rules.strict = false;
+        }
+        if let Some(changes) = value("--direction-changes") {
+            rules.direction_changes = changes.parse().expect("Direction changes must be a number");
+        }
+        rules
+    }
+
+    // direction of the step from left to right, None when it breaks the rules, Flat for an allowed flat step
+    fn step(&self, left: i32, right: i32) -> Option<Direction> {
+        let step = right - left;
+        if step == 0 {
+            return (!self.strict).then_some(Direction::Flat);
+        }
+        if !(self.min_step..=self.max_step).contains(&step.abs()) {
+            return None;
+        }
+        Some(if step > 0 { Direction::Up } else { Direction::Down })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Direction {
+    // no step has gone up or down yet
+    Flat,
+    Up,
+    Down,
+}
+
+// State is what --dump-state writes, every report with its verdict with and without the dampener
+#[derive(Serialize)]
+struct State {
+    rules: SafetyRules,
+    reports: Vec<ReportState>,
+    safe: usize,
+    dampener: usize,
+    safe_with_dampener: usize,
+    summary: Option<Summary>,
+}
+
+// Summary groups the reports by outcome, with how long they are and where the ones failing the strict check
+// first break the rules. It is printed by --summary instead of every report
+#[derive(Default, Serialize)]
+struct Summary {
+    reports: usize,
+    safe: usize,
+    // not safe as they are, but safe with the dampener
+    safe_only_with_dampener: usize,
+    unsafe_by_problem: BTreeMap<Problem, usize>,
+    lengths: BTreeMap<usize, usize>,
+    failure_positions: BTreeMap<usize, usize>,
+}
+
+impl Summary {
+    fn add(&mut self, checked: &Checked) {
+        self.reports += 1;
+        *self.lengths.entry(checked.levels.len()).or_insert(0) += 1;
+        if let Safety::Unsafe(violation) = &checked.strict {
+            *self.failure_positions.entry(violation.index).or_insert(0) += 1;
+        }
+        match (&checked.strict, &checked.dampened) {
+            (Safety::Safe { .. }, _) => self.safe += 1,
+            (_, Safety::Safe { .. }) => self.safe_only_with_dampener += 1,
+            (_, Safety::Unsafe(violation)) => *self.unsafe_by_problem.entry(violation.problem).or_insert(0) += 1,
+        }
+    }
+
+    fn print(&self) {
+        println!("Reports: {}", self.reports);
+        println!("Safe: {}", self.safe);
+        println!("Safe only with dampener: {}", self.safe_only_with_dampener);
+        for (problem, count) in &self.unsafe_by_problem {
+            println!("Unsafe, {}: {}", problem, count);
+        }
+        println!("Lengths:");
+        for (length, count) in &self.lengths {
+            println!("{:>4} levels: {}", length, count);
+        }
+        // ties go to the earliest position
+        let most_common = self.failure_positions.iter().max_by_key(|(index, count)| (**count, std::cmp::Reverse(**index)));
+        if let Some((index, count)) = most_common {
+            println!("Most common failure position: levels at index {} and {} ({} reports)", index, index + 1, count);
+        }
+    }
+}
+
+// Checked is a report with its verdicts without and with the dampener
+struct Checked {
+    levels: Vec<i32>,
+    strict: Safety,
+    dampened: Safety,
+}
+
+// BATCH is how many reports a worker checks at a time
+const BATCH: usize = 1024;
 
-    counter = 0;
-    for report in &reports {
-        for level in &report.levels {
-            print!("{} ", level);
+// check_reports checks the reports on a pool of worker threads, both without and with the dampener at once,
+// while they are still being read in batches, and hands them to `each` in the order of the input.
+// The batches waiting to be checked are bounded, so memory stays flat however many reports there are
+fn check_reports(filename: &str, rules: &SafetyRules, dampener: usize, workers: usize, mut each: impl FnMut(Checked)) {
+    let (sender, receiver) = mpsc::sync_channel::<(usize, Vec<Report>)>(workers * 2);
+    let receiver = Arc::new(Mutex::new(receiver));
+    let (checked_sender, checked) = mpsc::channel::<(usize, Vec<Checked>)>();
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            let receiver = Arc::clone(&receiver);
+            let checked_sender = checked_sender.clone();
+            scope.spawn(move || loop {
+                let next = receiver.lock().unwrap().recv();
+                let Ok((idx, batch)) = next else { break };
+                let batch = batch.into_iter()
+                    .map(|report| Checked {
+                        strict: check_safety(&report.levels, rules, 0),
+                        dampened: check_safety(&report.levels, rules, dampener),
+                        levels: report.levels,
+                    })
+                    .collect();
+                checked_sender.send((idx, batch)).unwrap();
+            });
         }
-        match check_safety_loosely(report.levels.clone()) {
-            Safety::Safe => {
-                print!("Safe");
-                counter += 1;
-            },
-            Safety::Unsafe => print!("Unsafe"),
+        drop(checked_sender);
+        scope.spawn(move || {
+            let mut reports = parse_input(filename).peekable();
+            let mut idx = 0;
+            while reports.peek().is_some() {
+                sender.send((idx, reports.by_ref().take(BATCH).collect())).unwrap();
+                idx += 1;
+            }
+        });
+
+        // batches finish out of order, they wait here until the ones before them are handed over
+        let mut waiting = BTreeMap::new();
+        let mut next = 0;
+        for (idx, batch) in checked.iter() {
+            waiting.insert(idx, batch);
+            while let Some(batch) = waiting.remove(&next) {
+                batch.into_iter().for_each(&mut each);
+                next += 1;
+            }
         }
-        print!("\n");
+    });
+}
+
+#[derive(Serialize)]
+struct ReportState {
+    levels: Vec<i32>,
+    safe: bool,
+    safe_with_dampener: bool,
+    violation: Option<Violation>,
+    removed: Vec<Removed>,
+}
+
+fn main() {
+    let input = std::env::args().nth(1).expect("Please provide an input");
+    // reports are streamed from the file and checked in parallel, they are only kept when the state is dumped
+    let dump = std::env::args().skip_while(|arg| arg != "--dump-state").nth(1);
+    // how many bad levels the Problem Dampener tolerates in the second check, one unless given with --dampener
+    let dampener: usize = std::env::args().skip_while(|arg| arg != "--dampener").nth(1)
+        .map_or(1, |dampener| dampener.parse().expect("Dampener must be a number of levels"));
+    let rules = SafetyRules::from_args();
+    // --explain prints why every report is unsafe, or which levels the dampener removed
+    let explain = std::env::args().any(|arg| arg == "--explain");
+    // --summary only prints the answers and the summary instead of every report
+    let summarize = std::env::args().any(|arg| arg == "--summary");
+    let workers = std::env::args().skip_while(|arg| arg != "--threads").nth(1)
+        .map(|threads| threads.parse().expect("Threads must be a number"))
+        .unwrap_or_else(|| thread::available_parallelism().map(|n| n.get()).unwrap_or(1));
+
+    // both checks are made in one pass, the verdicts with the dampener are printed after the others
+    // so they wait in a temporary file
+    let dampened_path = std::env::temp_dir().join(format!("day-2-{}.txt", std::process::id()));
+    let mut dampened_output = (!summarize)
+        .then(|| BufWriter::new(File::create(&dampened_path).expect("Failed to create a temporary file")));
+    let mut summary = Summary::default();
+    let mut dumped = Vec::new();
+    check_reports(&input, &rules, dampener, workers.max(1), |checked| {
+        summary.add(&checked);
+        if let Some(output) = dampened_output.as_mut() {
+            let levels: String = checked.levels.iter().map(|level| format!("{} ", level)).collect();
+            println!("{}{}", levels, verdict(&checked.strict, explain));
+            writeln!(output, "{}{}", levels, verdict(&checked.dampened, explain)).expect("Failed to write a temporary file");
+        }
+        if dump.is_some() {
+            let violation = match checked.strict {
+                Safety::Safe { .. } => None,
+                Safety::Unsafe(violation) => Some(violation),
+            };
+            let (safe_with_dampener, removed) = match checked.dampened {
+                Safety::Safe { removed } => (true, removed),
+                Safety::Unsafe(_) => (false, Vec::new()),
+            };
+            dumped.push(ReportState {
+                safe: violation.is_none(),
+                safe_with_dampener,
+                violation,
+                removed,
+                levels: checked.levels,
+            });
+        }
+    });
+    let safe = summary.safe;
+    let safe_with_dampener = summary.safe + summary.safe_only_with_dampener;
+
+    println!("Safe reports: {}", safe);
+    println!("----------------");
+    if let Some(output) = dampened_output {
+        drop(output.into_inner().expect("Failed to write a temporary file"));
+        let mut dampened = File::open(&dampened_path).expect("Failed to read a temporary file");
+        std::io::copy(&mut dampened, &mut std::io::stdout()).expect("Failed to print the reports");
+        let _ = std::fs::remove_file(&dampened_path);
+    }
+    println!("Safe reports: {}", safe_with_dampener);
+    if summarize {
+        println!("----------------");
+        summary.print();
+    }
+
+    if let Some(path) = dump {
+        let state = State {
+            rules,
+            reports: dumped,
+            safe,
+            dampener,
+            safe_with_dampener,
+            summary: summarize.then_some(summary),
+        };
+        serde_json::to_writer_pretty(File::create(&path).expect("Failed to create state file"), &state)
+            .expect("Failed to write state");
+        println!("State dumped to {}", path);
     }
-    print!("Safe reports: {}\n", counter);
 }
 
-fn parse_input(filename: &str) -> Vec<Report> {
-    let mut output = Vec::new();
+fn verdict(safety: &Safety, explain: bool) -> String {
+    if explain {
+        safety.to_string()
+    } else if matches!(safety, Safety::Safe { .. }) {
+        "Safe".to_string()
+    } else {
+        "Unsafe".to_string()
+    }
+}
 
-    for line in read_to_string(filename).unwrap().lines() {
+fn parse_input(filename: &str) -> impl Iterator<Item = Report> {
+    input::lines(filename).map(|line| {
         let iter = line.split_whitespace();
         let mut levels = Vec::new();
         for item in iter {
             levels.push(item.parse().unwrap());
         }
-        output.push(Report { levels });
-    }
+        Report { levels }
+    })
+}
 
-    output
+// check_safety tells whether the levels follow the rules once at most `dampener` of them are removed,
+// 0 being the strict check and 1 the Problem Dampener of part 2
+fn check_safety(levels: &[i32], rules: &SafetyRules, dampener: usize) -> Safety {
+    match fewest_removals(levels, rules, dampener) {
+        Some(removed) => Safety::Safe {
+            removed: removed.into_iter().map(|index| Removed { index, level: levels[index] }).collect(),
+        },
+        None => Safety::Unsafe(first_violation(levels, rules).expect("An unsafe report breaks the rules somewhere")),
+    }
 }
 
-fn check_safety(levels: Vec<i32>) -> Safety {
-    // first check if strict asc or desc
-    if !check_asc(levels.clone()) && !check_desc(levels.clone()) {
-        Safety::Unsafe
-    } else {
-        Safety::Safe
+// first_violation walks the levels as they are and returns the first step breaking the rules
+fn first_violation(levels: &[i32], rules: &SafetyRules) -> Option<Violation> {
+    let mut last = Direction::Flat;
+    let mut changes = 0;
+    for (index, pair) in levels.windows(2).enumerate() {
+        let delta = pair[1] - pair[0];
+        let problem = match rules.step(pair[0], pair[1]) {
+            Some(Direction::Flat) => continue,
+            Some(direction) => {
+                if last != Direction::Flat && last != direction {
+                    changes += 1;
+                }
+                last = direction;
+                if changes <= rules.direction_changes {
+                    continue;
+                }
+                Problem::DirectionChange
+            }
+            None if delta == 0 => Problem::Flat,
+            None if delta.abs() < rules.min_step => Problem::TooSmall,
+            None => Problem::TooLarge,
+        };
+        return Some(Violation { index, delta, problem });
     }
+    None
 }
 
-fn check_asc(levels: Vec<i32>) -> bool {
-    // check if level is in strict ascending order, and difference between levels is between 1 and 3
-    let mut is_ascending = true;
-    for i in 0..levels.len() - 1 {
-        let left = levels[i];
-        let right = levels[i + 1];
-        if right - left < 1 || right - left > 3 {
-            is_ascending = false;
-            break;
+// Removals is the fewest levels removed before a level with it kept, and the kept level before it,
+// with the direction changes and direction it was reached with
+#[derive(Clone, Copy)]
+struct Removals {
+    count: usize,
+    previous: Option<(usize, usize, Direction)>,
+}
+
+const UNREACHED: Removals = Removals { count: usize::MAX, previous: None };
+
+// fewest_removals returns the fewest levels to remove so the rest follow the rules,
+// None when more than `limit` are needed. kept[i][changes][direction] is the fewest removals before level i
+// with level i kept, after that many direction changes and last going in that direction. As more than `limit`
+// levels can't be skipped, level i only has to be checked against the limit + 1 levels before it,
+// O(n·limit·direction changes) in total
+fn fewest_removals(levels: &[i32], rules: &SafetyRules, limit: usize) -> Option<Vec<usize>> {
+    const DIRECTIONS: [Direction; 3] = [Direction::Flat, Direction::Up, Direction::Down];
+    let mut kept: Vec<Vec<[Removals; 3]>> = Vec::with_capacity(levels.len());
+    for (i, &level) in levels.iter().enumerate() {
+        let mut fewest = vec![[UNREACHED; 3]; rules.direction_changes + 1];
+        fewest[0][Direction::Flat as usize] = Removals { count: i, previous: None };
+        for j in i.saturating_sub(limit + 1)..i {
+            let Some(step) = rules.step(levels[j], level) else { continue };
+            let skipped = i - j - 1;
+            for (changes, removals_by_direction) in kept[j].iter().enumerate() {
+                for &last in DIRECTIONS.iter() {
+                    let removals = removals_by_direction[last as usize].count;
+                    if removals == usize::MAX {
+                        continue;
+                    }
+                    let (direction, next_changes) = match (last, step) {
+                        (last, Direction::Flat) => (last, changes),
+                        (Direction::Flat, step) => (step, changes),
+                        (last, step) if last == step => (step, changes),
+                        (_, step) => (step, changes + 1),
+                    };
+                    if next_changes <= rules.direction_changes {
+                        let best = &mut fewest[next_changes][direction as usize];
+                        if removals + skipped < best.count {
+                            *best = Removals { count: removals + skipped, previous: Some((j, changes, last)) };
+                        }
+                    }
+                }
+            }
         }
+        kept.push(fewest);
     }
-    is_ascending
-}
 
-fn check_desc(levels: Vec<i32>) -> bool {
-    // check if level is in strict descending order, and difference between levels is between 1 and 3
-    let mut is_descending = true;
-    for i in 0..levels.len() - 1 {
-        let left = levels[i];
-        let right = levels[i + 1];
-        if left - right < 1 || left - right > 3 {
-            is_descending = false;
-            break;
+    // the last kept level, with every level after it removed
+    let mut best: Option<(usize, usize, usize, Direction)> = None;
+    for (i, removals_by_changes) in kept.iter().enumerate() {
+        for (changes, removals_by_direction) in removals_by_changes.iter().enumerate() {
+            for &direction in DIRECTIONS.iter() {
+                let count = removals_by_direction[direction as usize].count.saturating_add(levels.len() - i - 1);
+                if best.is_none_or(|(fewest, ..)| count < fewest) {
+                    best = Some((count, i, changes, direction));
+                }
+            }
         }
     }
-    is_descending
+    let Some((count, i, changes, direction)) = best else { return Some(Vec::new()) };
+    if count > limit {
+        return None;
+    }
+    let mut removed: Vec<usize> = ((i + 1)..levels.len()).collect();
+    let mut at = Some((i, changes, direction));
+    while let Some((i, changes, direction)) = at {
+        let previous = kept[i][changes][direction as usize].previous;
+        let before = previous.map_or(0, |(j, ..)| j + 1);
+        removed.extend(before..i);
+        at = previous;
+    }
+    removed.sort_unstable();
+    Some(removed)
 }
 
-fn check_safety_loosely(levels: Vec<i32>) -> Safety {
-    // first check if asc or desc
-    if !check_asc_loosely(levels.clone()) && !check_desc_loosely(levels.clone()) {
-        Safety::Unsafe
-    } else {
-        Safety::Safe
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // safe tells whether the levels follow the puzzle's rules as they are, written out without SafetyRules
+    fn safe(levels: &[i32]) -> bool {
+        let steps: Vec<i32> = levels.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        steps.iter().all(|step| (1..=3).contains(step)) || steps.iter().all(|step| (-3..=-1).contains(step))
     }
-}
 
-fn check_asc_loosely(levels: Vec<i32>) -> bool {
-    // check if level is in ascending order, and difference between levels is between 1 and 3
-    // the loose version of check allows for "what if we remove a single element from the list"
-    // so we raw dog it and attempt to remove every element one time and check if the list is still in compliance
-    if check_asc(levels.clone()) {
-        return true;
+    // brute_force tries every set of at most `limit` levels to remove, and returns the size of the smallest
+    // one leaving a safe report
+    fn brute_force(levels: &[i32], limit: usize) -> Option<usize> {
+        (0..1u32 << levels.len())
+            .filter(|removed| removed.count_ones() as usize <= limit)
+            .filter(|removed| {
+                let kept: Vec<i32> = levels.iter().enumerate().filter(|(i, _)| removed & (1 << i) == 0).map(|(_, &level)| level).collect();
+                safe(&kept)
+            })
+            .map(|removed| removed.count_ones() as usize)
+            .min()
     }
-    for i in 0..levels.len() {
-        let mut temp = levels.clone();
-        temp.remove(i);
-        if check_asc(temp) {
-            return true;
+
+    fn check(levels: &[i32], limit: usize) {
+        let removed = fewest_removals(levels, &SafetyRules::default(), limit);
+        assert_eq!(removed.as_ref().map(Vec::len), brute_force(levels, limit), "{:?} with {} removals", levels, limit);
+        if let Some(removed) = removed {
+            let kept: Vec<i32> = levels.iter().enumerate().filter(|(i, _)| !removed.contains(i)).map(|(_, &level)| level).collect();
+            assert!(safe(&kept), "{:?} without {:?}", levels, removed);
         }
     }
-    false
-}
 
-fn check_desc_loosely(levels: Vec<i32>) -> bool {
-    // check if level is in descending order, and difference between levels is between 1 and 3
-    // the loose version of check allows for "what if we remove a single element from the list"
-    // so we raw dog it and attempt to remove every element one time and check if the list is still in compliance
-    if check_desc(levels.clone()) {
-        return true;
+    #[test]
+    fn matches_brute_force_on_small_reports() {
+        // every report of up to 6 levels between 0 and 4, with flat, allowed and too large steps both ways
+        for length in 0..=6u32 {
+            for code in 0..5u32.pow(length) {
+                let levels: Vec<i32> = (0..length).map(|i| (code / 5u32.pow(i) % 5) as i32).collect();
+                for limit in 0..=2 {
+                    check(&levels, limit);
+                }
+            }
+        }
     }
-    for i in 0..levels.len() {
-        let mut temp = levels.clone();
-        temp.remove(i);
-        if check_desc(temp) {
-            return true;
+
+    #[test]
+    fn removes_the_first_or_last_levels() {
+        for levels in [
+            [9, 1, 2, 3, 4].as_slice(),
+            &[1, 2, 3, 4, 9],
+            &[9, 8, 1, 2, 3, 4],
+            &[1, 2, 3, 4, 9, 0],
+            &[5, 1, 2, 3, 0],
+            &[7, 6, 4, 2, 1],
+            &[1, 2, 7, 8, 9],
+        ] {
+            for limit in 0..=2 {
+                check(levels, limit);
+            }
         }
+        assert_eq!(fewest_removals(&[9, 1, 2, 3, 4], &SafetyRules::default(), 1), Some(vec![0]));
+        assert_eq!(fewest_removals(&[1, 2, 3, 4, 9], &SafetyRules::default(), 1), Some(vec![4]));
+        assert_eq!(fewest_removals(&[9, 8, 1, 2, 3, 4], &SafetyRules::default(), 2), Some(vec![0, 1]));
+        assert_eq!(fewest_removals(&[1, 2, 3, 4, 9, 0], &SafetyRules::default(), 2), Some(vec![4, 5]));
+        assert_eq!(fewest_removals(&[9, 8, 1, 2, 3, 4], &SafetyRules::default(), 1), None);
     }
-    false
-}
\ No newline at end of file
+}
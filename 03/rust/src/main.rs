@@ -1,52 +1,77 @@
-use std::fs::read_to_string;
+use std::fs::File;
 use regex::Regex;
+use serde::Serialize;
 
+#[derive(Serialize)]
 struct Multiplication {
     factor_a: i32,
     factor_b: i32,
 }
 
+// State is what --dump-state writes, the enabled multiplications and their sum
+#[derive(Serialize)]
+struct State<'a> {
+    multiplications: &'a [Multiplication],
+    sum: i32,
+}
+
 fn main() {
     let input = std::env::args().nth(1).expect("Please provide an input");
-    let multiplications = parse_input(&input);
+    // multiplications are summed as the memory is streamed, they are only kept when the state is dumped.
+    // The memory is streamed twice so the instructions found are all printed before the multiplications
+    let dump = std::env::args().skip_while(|arg| arg != "--dump-state").nth(1);
+    let mut multiplications = Vec::new();
+    parse_input(&input, true, |_| {});
 
     let mut sum = 0;
-    for multiplication in &multiplications {
+    parse_input(&input, false, |multiplication| {
         println!("Multiplication: {} * {}", multiplication.factor_a, multiplication.factor_b);
         sum += multiplication.factor_a * multiplication.factor_b;
-    }
+        if dump.is_some() {
+            multiplications.push(multiplication);
+        }
+    });
 
     println!("Sum: {}", sum);
 
+    if let Some(path) = dump {
+        let state = State { multiplications: &multiplications, sum };
+        serde_json::to_writer_pretty(File::create(&path).expect("Failed to create state file"), &state)
+            .expect("Failed to write state");
+        println!("State dumped to {}", path);
+    }
 }
 
-fn parse_input(filename: &str) -> Vec<Multiplication> {
-    let memory = read_to_string(filename).unwrap();
-    // traverse through the string "memory" by characters
+// parse_input streams the memory one line at a time and hands every enabled multiplication to found,
+// do() and don't() carry over from one line to the next. With echo the instructions are printed as they are found
+fn parse_input(filename: &str, echo: bool, mut found: impl FnMut(Multiplication)) {
+    // traverse through the memory line by line
     // and use a flag to determine if we are inside the expected pattern of mul(a,b) or not
-    // if we are inside the pattern, we will collect the numbers and append them to a vector of Multiplication
+    // if we are inside the pattern, we will collect the numbers and pass them on as a Multiplication
     // if we are not inside the pattern, we will ignore the characters and continue
-    // finally, we will return the vector of Multiplication
-    let mut output: Vec<Multiplication> = Vec::new();
     let re = Regex::new(r"(mul\(\d{1,3},\d{1,3}\)|do\(\)|don't\(\))").unwrap();
     let re2 = Regex::new(r"\d{1,3}").unwrap();
-    let matches = re.find_iter(&memory).map(|m| m.as_str()).collect::<Vec<&str>>();
     let mut counts = true;
-    for m in matches {
-        println!("{}", m);
-        if m == "don't()" {
-            counts = false;
-            continue;
-        } else if m == "do()" {
-            counts = true;
-            continue;
-        } else if counts {
-            let mut numbers = re2.find_iter(m).map(|m| m.as_str()).collect::<Vec<&str>>();
-            println!("{:?}", numbers);
-            let factor_b = numbers.pop().unwrap().parse().unwrap();
-            let factor_a = numbers.pop().unwrap().parse().unwrap();
-            output.push(Multiplication { factor_a, factor_b });    
+    for memory in input::lines(filename) {
+        for m in re.find_iter(&memory).map(|m| m.as_str()) {
+            if echo {
+                println!("{}", m);
+            }
+            if m == "don't()" {
+                counts = false;
+                continue;
+            } else if m == "do()" {
+                counts = true;
+                continue;
+            } else if counts {
+                let mut numbers = re2.find_iter(m).map(|m| m.as_str()).collect::<Vec<&str>>();
+                if echo {
+                    println!("{:?}", numbers);
+                }
+                let factor_b = numbers.pop().unwrap().parse().unwrap();
+                let factor_a = numbers.pop().unwrap().parse().unwrap();
+                found(Multiplication { factor_a, factor_b });
+            }
         }
     }
-    output
 }
@@ -1,16 +1,35 @@
-use std::fs::read_to_string;
+use std::{fs::File, collections::HashSet};
+use render::{Canvas, Rgb};
+use serde::Serialize;
 
+#[derive(Serialize)]
 struct Matrix {
     rows: Vec<Vec<char>>,
     height: usize,
     width: usize,
 }
 
+// State is what --dump-state writes, the matrix with the cells of every match and both counts
+#[derive(Serialize)]
+struct State<'a> {
+    matrix: &'a Matrix,
+    search: &'a str,
+    xmas_cells: Vec<(usize, usize)>,
+    xmas_count: u32,
+    x_mas_cells: Vec<(usize, usize)>,
+    x_mas_count: u32,
+}
+
 fn main() {
     let input = std::env::args().nth(1).expect("Please provide an input");
 
     let matrix = parse_input(&input);
 
+    if std::env::args().any(|arg| arg == "--repl") {
+        repl(&matrix);
+        return;
+    }
+
     for row in 0..matrix.height {
         for col in 0..matrix.width {
             print!("{}", matrix.rows[row][col]);
@@ -27,15 +46,33 @@ fn main() {
 
     let x_mas_counter = search_x_mas_in_matrix(&matrix);
     println!("Found: {} X-MAS", x_mas_counter);
+
+    if let Some(path) = std::env::args().skip_while(|arg| arg != "--render").nth(1) {
+        render(&matrix, search, &path);
+        println!("Rendered to {}", path);
+    }
+    if let Some(path) = std::env::args().skip_while(|arg| arg != "--dump-state").nth(1) {
+        let state = State {
+            matrix: &matrix,
+            search,
+            xmas_cells: match_cells(&matrix, search),
+            xmas_count: xmas_counter,
+            x_mas_cells: x_mas_cells(&matrix),
+            x_mas_count: x_mas_counter,
+        };
+        serde_json::to_writer_pretty(File::create(&path).expect("Failed to create state file"), &state)
+            .expect("Failed to write state");
+        println!("State dumped to {}", path);
+    }
 }
 
 
 fn parse_input(filename: &str) -> Matrix {
     let mut rows: Vec<Vec<char>> = Vec::new();
-    for line in read_to_string(filename).unwrap().lines() {
+    for line in input::Grid::open(filename).rows() {
         let mut row = Vec::new();
-        for c in line.chars() {
-            row.push(c);
+        for c in line.iter() {
+            row.push(*c as char);
         }
         rows.push(row);
     }
@@ -119,4 +156,107 @@ fn search_x_mas_in_matrix(matrix: &Matrix) -> u32 {
         }
     }
     counter
-}
\ No newline at end of file
+}
+
+// match_cells returns every cell that belongs to an occurrence of search, in any of the 8 directions
+fn match_cells(matrix: &Matrix, search: &str) -> Vec<(usize, usize)> {
+    let letters: Vec<char> = search.chars().collect();
+    let mut cells = Vec::new();
+    for row in 0..matrix.height {
+        for col in 0..matrix.width {
+            for x_axis in -1..2 {
+                for y_axis in -1..2 {
+                    if x_axis == 0 && y_axis == 0 {
+                        continue;
+                    }
+                    let path: Vec<(i32, i32)> = (0..letters.len() as i32)
+                        .map(|step| (row as i32 + y_axis * step, col as i32 + x_axis * step))
+                        .collect();
+                    let found = path.iter().zip(letters.iter()).all(|(&(r, c), letter)| {
+                        r >= 0 && c >= 0 && valid_position(matrix, r as usize, c as usize) && matrix.rows[r as usize][c as usize] == *letter
+                    });
+                    if found {
+                        cells.extend(path.iter().map(|&(r, c)| (r as usize, c as usize)));
+                    }
+                }
+            }
+        }
+    }
+    cells
+}
+
+// x_mas_cells returns the five cells of every X-MAS cross
+fn x_mas_cells(matrix: &Matrix) -> Vec<(usize, usize)> {
+    let is_mas = |a: char, b: char| (a == 'M' && b == 'S') || (a == 'S' && b == 'M');
+    let mut cells = Vec::new();
+    for row in 1..matrix.height-1 {
+        for col in 1..matrix.width-1 {
+            if matrix.rows[row][col] == 'A'
+                && is_mas(matrix.rows[row-1][col-1], matrix.rows[row+1][col+1])
+                && is_mas(matrix.rows[row-1][col+1], matrix.rows[row+1][col-1]) {
+                cells.extend([(row, col), (row-1, col-1), (row+1, col+1), (row-1, col+1), (row+1, col-1)]);
+            }
+        }
+    }
+    cells
+}
+
+// render draws the letter grid, XMAS matches in yellow, X-MAS crosses in green
+// and the letters shared by both kinds of match in orange
+fn render(matrix: &Matrix, search: &str, filename: &str) {
+    let mut canvas = Canvas::new(matrix.width, matrix.height, Rgb::WHITE);
+    let xmas: HashSet<(usize, usize)> = match_cells(matrix, search).into_iter().collect();
+    let x_mas = x_mas_cells(matrix);
+    for &(row, col) in xmas.iter() {
+        canvas.fill(row, col, Rgb::YELLOW);
+    }
+    for &(row, col) in x_mas.iter() {
+        if xmas.contains(&(row, col)) {
+            canvas.fill(row, col, Rgb::ORANGE);
+        } else {
+            canvas.fill(row, col, Rgb::GREEN);
+        }
+    }
+    for row in 0..matrix.height {
+        for col in 0..matrix.width {
+            canvas.label(row, col, matrix.rows[row][col]);
+        }
+    }
+    canvas.save(filename).expect("Failed to write render");
+}
+
+// repl answers queries about the parsed matrix until quit or end of input
+fn repl(matrix: &Matrix) {
+    let help = "Commands (rows and columns start at 0):
+  cell <row> <col>        letter at the position
+  matches-at <row> <col>  XMAS matches starting at the position
+  search <word>           occurrences of the word in the matrix
+  x-mas                   number of X-MAS crosses
+  quit";
+    println!("{}x{} matrix loaded, type help for commands", matrix.height, matrix.width);
+    let position = |row: &str, col: &str| match (row.parse::<usize>(), col.parse::<usize>()) {
+        (Ok(row), Ok(col)) if valid_position(matrix, row, col) => Some((row, col)),
+        _ => {
+            println!("Invalid position, the matrix is {}x{}", matrix.height, matrix.width);
+            None
+        }
+    };
+    repl::run(&mut (), help, |_| "> ".to_string(), |_, words| {
+        match words {
+            ["cell", row, col] => {
+                if let Some((row, col)) = position(row, col) {
+                    println!("{}", matrix.rows[row][col]);
+                }
+            }
+            ["matches-at", row, col] => {
+                if let Some((row, col)) = position(row, col) {
+                    println!("{}", search_matches_from_position(matrix, "XMAS", row, col));
+                }
+            }
+            ["search", word] => println!("{}", search_in_matrix(matrix, word)),
+            ["x-mas"] => println!("{}", search_x_mas_in_matrix(matrix)),
+            _ => return false,
+        }
+        true
+    });
+}
@@ -1,5 +1,12 @@
-use std::fs::read_to_string;
+use std::fs::{read_to_string, File};
+use counters::{Counter, Stats};
+use serde::Serialize;
 
+// SWAPS counts the values swapped by fix_with_rules, reported with --stats and in --dump-state
+static SWAPS: Counter = Counter::new("swaps", "Swaps in fix_with_rules");
+static COUNTERS: [&Counter; 1] = [&SWAPS];
+
+#[derive(Serialize)]
 struct Rule {
     left: u32,
     right: u32,
@@ -15,6 +22,8 @@ impl Rule {
     }
 }
 
+#[derive(Serialize)]
+#[serde(transparent)]
 struct Update {
     values: Vec<u32>,
 }
@@ -109,6 +118,7 @@ impl Update {
                 if left_index > right_index {
 //                    print!("Swapping {} and {}... ", left, right);
                     values.swap(left_index, right_index);
+                    SWAPS.add();
                 } else {
 //                    println!("Values are in correct order: {}", values.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(", "));
                 }
@@ -124,41 +134,130 @@ struct Input {
     updates: Vec<Update>,
 }
 
+// State is what --dump-state writes, the parsed manual along with the results of both parts
+#[derive(Serialize)]
+struct State<'a> {
+    rules: &'a [Rule],
+    valid_updates: Vec<&'a Update>,
+    invalid_updates: Vec<Fix<'a>>,
+    middle_sum_before_fix: u32,
+    middle_sum_fixed: u32,
+    stats: Stats,
+}
+
+#[derive(Serialize)]
+struct Fix<'a> {
+    update: &'a Update,
+    fixed: Update,
+}
+
 fn main() {
     let input = std::env::args().nth(1).expect("Please provide an input");
 
     let sleigh_launch_safety_manual = parse_input(&input);
 
+    if std::env::args().any(|arg| arg == "--repl") {
+        repl(&sleigh_launch_safety_manual);
+        return;
+    }
+
     sleigh_launch_safety_manual.rules.iter().for_each(|rule| rule.print());
     sleigh_launch_safety_manual.updates.iter().for_each(|update| update.print());
 
     println!("Valid updates:");
 
     let mut middle_sum = 0;
+    let mut valid_updates = Vec::new();
     let mut invalid_updates = Vec::new();
     for update in sleigh_launch_safety_manual.updates.iter() {
         if update.is_valid(&sleigh_launch_safety_manual.rules) {
             update.print();
             middle_sum += update.get_middle();
+            valid_updates.push(update);
         } else {
             invalid_updates.push(update);
         }
     }
 
     println!("Sum of middle values, before fix: {}", middle_sum);
+    let middle_sum_before_fix = middle_sum;
 
     // fix the invalid updates
     middle_sum = 0;
+    let mut fixes = Vec::new();
     println!("Invalid updates, now fixed:");
     for update in invalid_updates.iter() {
         let fixed_update: Update = update.fix_with_rules(&sleigh_launch_safety_manual.rules);
         fixed_update.print();
         middle_sum += fixed_update.get_middle();
+        fixes.push(Fix { update, fixed: fixed_update });
     }
 
     println!("Sum of middle values, only fixed updates: {}", middle_sum);
-    
+    if counters::requested() {
+        Stats::collect(&COUNTERS).print();
+    }
 
+    if let Some(path) = std::env::args().skip_while(|arg| arg != "--dump-state").nth(1) {
+        let state = State {
+            rules: &sleigh_launch_safety_manual.rules,
+            valid_updates,
+            invalid_updates: fixes,
+            middle_sum_before_fix,
+            middle_sum_fixed: middle_sum,
+            stats: Stats::collect(&COUNTERS),
+        };
+        serde_json::to_writer_pretty(File::create(&path).expect("Failed to create state file"), &state)
+            .expect("Failed to write state");
+        println!("State dumped to {}", path);
+    }
+}
+
+// repl answers queries about the parsed rules and updates until quit or end of input
+fn repl(manual: &Input) {
+    let help = "Commands (updates are numbered from 0):
+  update <n>    pages of the update
+  is-valid <n>  whether the update follows the rules
+  fix <n>       the update reordered to follow the rules
+  middle <n>    middle page of the update
+  rules <page>  rules involving the page
+  quit";
+    println!("{} rules and {} updates loaded, type help for commands", manual.rules.len(), manual.updates.len());
+    let update = |n: &str| match n.parse::<usize>().ok().and_then(|n| manual.updates.get(n)) {
+        Some(update) => Some(update),
+        None => {
+            println!("Invalid update, there are {} updates", manual.updates.len());
+            None
+        }
+    };
+    repl::run(&mut (), help, |_| "> ".to_string(), |_, words| {
+        match words {
+            ["update", n] => update(n).iter().for_each(|update| update.print()),
+            ["is-valid", n] => {
+                if let Some(update) = update(n) {
+                    println!("{}", update.is_valid(&manual.rules));
+                }
+            }
+            ["fix", n] => {
+                if let Some(update) = update(n) {
+                    update.fix_with_rules(&manual.rules).print();
+                }
+            }
+            ["middle", n] => {
+                if let Some(update) = update(n) {
+                    println!("{}", update.get_middle());
+                }
+            }
+            ["rules", page] => match page.parse::<u32>() {
+                Ok(page) => manual.rules.iter()
+                    .filter(|rule| rule.left == page || rule.right == page)
+                    .for_each(|rule| rule.print()),
+                Err(_) => println!("Invalid page number"),
+            },
+            _ => return false,
+        }
+        true
+    });
 }
 
 fn parse_input(filename: &str) -> Input {
@@ -1,4 +1,15 @@
-use std::fs::read_to_string;
+use std::fs::File;
+use debugger::{Condition, Debugger, Machine};
+use progress::Progress;
+use render::{ansi, Canvas, Rgb};
+use counters::{Counter, Stats};
+use serde::{Deserialize, Serialize};
+use trace::Recorder;
+
+// STATES_VISITED counts the guard states (position and heading) stepped through, by the patrol
+// and by every test run of find_glitches, reported with --stats and in --dump-state
+static STATES_VISITED: Counter = Counter::new("states_visited", "States visited");
+static COUNTERS: [&Counter; 1] = [&STATES_VISITED];
 
 enum Direction {
     Up,
@@ -8,6 +19,16 @@ enum Direction {
 }
 
 impl Direction {
+    fn from_char(c: char) -> Option<Direction> {
+        match c {
+            '^' => Some(Direction::Up),
+            'v' => Some(Direction::Down),
+            '<' => Some(Direction::Left),
+            '>' => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
     fn as_char(&self) -> char {
         match self {
             Direction::Up => '^',
@@ -18,6 +39,26 @@ impl Direction {
     }
 }
 
+// Move is what the guard does in a single step of its patrol, it is also the event of a recorded trace
+#[derive(PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Move {
+    Walked(i32, i32),
+    Turned(char),
+    // the guard walks into a cell it already crossed with the same heading, it will patrol forever
+    Looped(i32, i32),
+}
+
+impl std::fmt::Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Move::Walked(i, j) => write!(f, "walked to ({}, {})", i, j),
+            Move::Turned(heading) => write!(f, "turned to {}", heading),
+            Move::Looped(i, j) => write!(f, "looped at ({}, {})", i, j),
+        }
+    }
+}
+
 struct Puzzle {
     height: i32,
     width: i32,
@@ -26,6 +67,23 @@ struct Puzzle {
     trace: Vec<Vec<char>>,
     position: (i32, i32),
     cur_dir: Direction,
+    looped: bool,
+    colour: bool,
+}
+
+// State is what --dump-state writes, the map after the guard left with the path it walked
+// and the cells where an obstruction would trap the guard in a loop
+#[derive(Serialize)]
+struct State<'a> {
+    height: i32,
+    width: i32,
+    map: Vec<String>,
+    position: (i32, i32),
+    direction: char,
+    visited: Vec<(i32, i32)>,
+    visited_count: i32,
+    glitches: &'a [(i32, i32)],
+    stats: Stats,
 }
 
 impl Puzzle {
@@ -48,6 +106,8 @@ impl Puzzle {
             position,
             cur_dir,
             trace,
+            looped: false,
+            colour: false,
         }
     }
 
@@ -74,6 +134,8 @@ impl Puzzle {
             position: self.position,
             cur_dir,
             trace,
+            looped: self.looped,
+            colour: self.colour,
         }
     }
 
@@ -81,12 +143,12 @@ impl Puzzle {
         for i in 0..self.height {
             for j in 0..self.width {
                 if (i, j) == self.position {
-                    print!("{}", self.cur_dir.as_char());
+                    print!("{}", self.paint(self.cur_dir.as_char()));
                 } else {
                     if self.visited[i as usize][j as usize] {
-                        print!("X");
+                        print!("{}", self.paint('X'));
                     } else {
-                        print!("{}", self.data[i as usize][j as usize]);
+                        print!("{}", self.paint(self.data[i as usize][j as usize]));
                     }
                 }
             }
@@ -94,6 +156,19 @@ impl Puzzle {
         }
     }
 
+    // paint colours walls in red, visited cells in blue and the guard in yellow
+    fn paint(&self, c: char) -> String {
+        if !self.colour {
+            return c.to_string();
+        }
+        match c {
+            '#' => ansi::fg(c, Rgb::RED),
+            'X' => ansi::fg(c, Rgb::BLUE),
+            '.' => ansi::fg(c, Rgb::GREY),
+            _ => ansi::fg(c, Rgb::YELLOW),
+        }
+    }
+
     fn count_visited(&self) -> i32 {
         self.visited.iter().flatten().filter(|&&x| x).count() as i32 + 1
     }
@@ -135,25 +210,72 @@ impl Puzzle {
         i >= 0 && i < self.height && j >= 0 && j < self.width
     }
 
+    // step moves the guard once, None when the guard has left the map or is stuck in a loop
+    fn step(&mut self) -> Option<Move> {
+        if self.looped || !self.next_move_within_bounds() {
+            return None;
+        }
+        STATES_VISITED.add();
+        let (i, j) = self.next_move();
+        let blocked = self.data[i as usize][j as usize] == '#';
+        if !self.visit(i, j) {
+            self.looped = true;
+            return Some(Move::Looped(i, j));
+        }
+        if blocked {
+            Some(Move::Turned(self.cur_dir.as_char()))
+        } else {
+            Some(Move::Walked(i, j))
+        }
+    }
+
+    // apply replays a recorded move without looking at the obstacles
+    fn apply(&mut self, step: &Move) {
+        match step {
+            Move::Walked(i, j) => {
+                let (i, j) = (*i, *j);
+                self.visited[i as usize][j as usize] = true;
+                self.data[i as usize][j as usize] = 'X';
+                self.position = (i, j);
+                self.trace[i as usize][j as usize] = self.cur_dir.as_char();
+            }
+            Move::Turned(heading) => self.cur_dir = Direction::from_char(*heading).expect("Invalid heading in trace"),
+            Move::Looped(i, j) => {
+                self.position = (*i, *j);
+                self.looped = true;
+            }
+        }
+    }
+
+    // solve_recording moves the guard until it leaves the map or loops, writing every move to the recorder
+    fn solve_recording(&mut self, recorder: &mut Recorder) {
+        while let Some(step) = self.step() {
+            recorder.record(&step);
+        }
+    }
+
     fn solve(&mut self) -> bool {
-        while self.next_move_within_bounds() {
-            let (i, j) = self.next_move();
-            if !self.visit(i, j) {
+        while let Some(step) = self.step() {
+            if let Move::Looped(..) = step {
                 return false;
             }
         }
         true
     }
 
-    fn find_glitches(&self) -> u32 {
-        let mut glitches = 0;
+    // find_glitches stops early when progress runs out of time, returning the glitches found so far
+    fn find_glitches(&self, progress: &mut Progress) -> Vec<(i32, i32)> {
+        let mut glitches = Vec::new();
         for i in 0..self.height {
             for j in 0..self.width {
+                if !progress.step() {
+                    return glitches;
+                }
                 let mut test_run = self.clone();
                 if test_run.data[i as usize][j as usize] == '.' {
                     test_run.data[i as usize][j as usize] = '#';
                     if !test_run.solve() {
-                        glitches += 1;
+                        glitches.push((i, j));
                     }
                 }
             }
@@ -161,32 +283,185 @@ impl Puzzle {
         glitches
     }
 
+    // render draws obstacles in black, visited cells in blue with the guard's last heading on them,
+    // and the cells where a new obstruction would trap the guard in a loop in red
+    fn render(&self, glitches: &[(i32, i32)], filename: &str) {
+        let mut canvas = Canvas::new(self.width as usize, self.height as usize, Rgb::WHITE);
+        for i in 0..self.height as usize {
+            for j in 0..self.width as usize {
+                if self.data[i][j] == '#' {
+                    canvas.fill(i, j, Rgb::BLACK);
+                } else if self.visited[i][j] {
+                    canvas.fill(i, j, Rgb::BLUE);
+                    canvas.label(i, j, self.trace[i][j]);
+                }
+            }
+        }
+        for (i, j) in glitches.iter() {
+            canvas.fill(*i as usize, *j as usize, Rgb::RED);
+        }
+        canvas.save(filename).expect("Failed to write render");
+    }
+
+    fn dump_state(&self, glitches: &[(i32, i32)], filename: &str) {
+        let mut visited = Vec::new();
+        for i in 0..self.height {
+            for j in 0..self.width {
+                if self.visited[i as usize][j as usize] {
+                    visited.push((i, j));
+                }
+            }
+        }
+        let state = State {
+            height: self.height,
+            width: self.width,
+            map: self.data.iter().map(|row| row.iter().collect()).collect(),
+            position: self.position,
+            direction: self.cur_dir.as_char(),
+            visited,
+            visited_count: self.count_visited(),
+            glitches,
+            stats: Stats::collect(&COUNTERS),
+        };
+        serde_json::to_writer_pretty(File::create(filename).expect("Failed to create state file"), &state)
+            .expect("Failed to write state");
+    }
+
+}
+
+impl Machine for Puzzle {
+    type Snapshot = Puzzle;
+    type Event = Move;
+
+    const CONDITIONS: &'static str = "    enters <row> <col>  the guard enters the cell
+    turns               the guard turns
+    visited <n>         at least n cells have been visited";
+
+    fn step(&mut self) -> Option<Move> {
+        Puzzle::step(self)
+    }
+
+    fn snapshot(&self) -> Puzzle {
+        self.clone()
+    }
+
+    fn restore(&mut self, snapshot: &Puzzle) {
+        *self = snapshot.clone();
+    }
+
+    fn show(&self) {
+        self.print();
+        println!("Visited: {}", self.count_visited());
+    }
+
+    fn condition(words: &[&str]) -> Option<Condition<Self>> {
+        match words {
+            ["enters", row, col] => {
+                let cell: (i32, i32) = (row.parse().ok()?, col.parse().ok()?);
+                Some(Box::new(move |_, event| matches!(event, Move::Walked(i, j) | Move::Looped(i, j) if (*i, *j) == cell)))
+            }
+            ["turns"] => Some(Box::new(|_, event| matches!(event, Move::Turned(_)))),
+            ["visited", n] => {
+                let n: i32 = n.parse().ok()?;
+                Some(Box::new(move |puzzle: &Puzzle, _| puzzle.count_visited() >= n))
+            }
+            _ => None,
+        }
+    }
 }
 
 fn main() {
+    if let Some(path) = trace::replay() {
+        replay(&path);
+        return;
+    }
     let input = std::env::args().nth(1).expect("Please provide an input");
 
     let mut puzzle = parse_input(&input);
+    puzzle.colour = ansi::enabled(std::env::args().any(|arg| arg == "--color"));
+    if std::env::args().any(|arg| arg == "--debug") {
+        Debugger::new(puzzle).run();
+        return;
+    }
 
     let clone = puzzle.clone();
     puzzle.print();
-    puzzle.solve();
+    let map: Vec<String> = puzzle.data.iter().map(|row| row.iter().collect()).collect();
+    match trace::recorder(6, &map) {
+        Some(mut recorder) => puzzle.solve_recording(&mut recorder),
+        None => {
+            puzzle.solve();
+        }
+    }
     puzzle.print();
 
     println!("Visited: {}", puzzle.count_visited());
-    println!("Glitches: {}", clone.find_glitches());
+    let mut progress = Progress::new("Finding glitches", (clone.height * clone.width) as u64).timeout(progress::timeout());
+    let glitches = clone.find_glitches(&mut progress);
+    progress.finish();
+    if progress.done() < progress.total() {
+        println!(
+            "Timed out after {:.1}s, checked {} of {} cells",
+            progress.elapsed().as_secs_f64(),
+            progress.done(),
+            progress.total()
+        );
+        println!("Glitches so far: {}", glitches.len());
+        std::process::exit(progress::TIMED_OUT);
+    }
+    println!("Glitches: {}", glitches.len());
+    if counters::requested() {
+        Stats::collect(&COUNTERS).print();
+    }
+
+    if let Some(path) = std::env::args().skip_while(|arg| arg != "--render").nth(1) {
+        puzzle.render(&glitches, &path);
+        println!("Rendered to {}", path);
+    }
+    if let Some(path) = std::env::args().skip_while(|arg| arg != "--dump-state").nth(1) {
+        puzzle.dump_state(&glitches, &path);
+        println!("State dumped to {}", path);
+    }
+}
+
+// replay rebuilds the state of the guard after the first --at moves of a trace, the trace starts from the map
+// the guard patrolled. With --check the patrol is simulated alongside and must make the same moves.
+fn replay(path: &str) {
+    let (map, events) = trace::open::<Vec<String>, Move>(path, 6);
+    let mut puzzle = parse(map.iter().map(|row| row.as_bytes()));
+    let mut simulation = trace::check().then(|| puzzle.clone());
+    let mut replayed = 0;
+    for event in events.take(trace::at()) {
+        if let Some(simulation) = simulation.as_mut() {
+            let step = simulation.step();
+            if step.as_ref() != Some(&event) {
+                let simulated = step.map_or("nothing".to_string(), |step| step.to_string());
+                println!("Move {} differs, recorded {} but simulated {}", replayed + 1, event, simulated);
+                std::process::exit(1);
+            }
+        }
+        puzzle.apply(&event);
+        replayed += 1;
+    }
+    puzzle.print();
+    println!("Replayed {} moves", replayed);
+    println!("Visited: {}", puzzle.count_visited());
 }
 
 fn parse_input(filename: &str) -> Puzzle {
+    parse(input::Grid::open(filename).rows())
+}
+
+fn parse<'a>(rows: impl Iterator<Item = &'a [u8]>) -> Puzzle {
     let mut data: Vec<Vec<char>> = Vec::new();
     let mut height = 0;
     let mut width = 0;
     let mut position = (0, 0);
     let mut cur_dir = Direction::Up;
 
-    read_to_string(filename).unwrap().lines().enumerate().for_each(|(i, line)| {
+    rows.enumerate().for_each(|(i, line)| {
         let mut row: Vec<char> = Vec::new();
-        line.chars().enumerate().for_each(|(j, c)| {
+        line.iter().map(|&c| c as char).enumerate().for_each(|(j, c)| {
             row.push(c);
             if c == '^' {
                 position = (i as i32, j as i32);
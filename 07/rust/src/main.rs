@@ -1,5 +1,14 @@
-use std::fs::read_to_string;
+use std::fs::File;
 
+use counters::{Counter, Stats};
+use serde::Serialize;
+
+// recursive calls of both checks, reported with --stats and in --dump-state
+static RECURSIVE_CALLS: Counter = Counter::new("recursive_calls", "Recursive calls");
+static RECURSIVE_CALLS_WITH_CONCAT: Counter = Counter::new("recursive_calls_with_concat", "Recursive calls with concatenation");
+static COUNTERS: [&Counter; 2] = [&RECURSIVE_CALLS, &RECURSIVE_CALLS_WITH_CONCAT];
+
+#[derive(Serialize)]
 struct Calibration {
     result: i64,
     input: Vec<i64>
@@ -25,6 +34,7 @@ impl Calibration {
     }
 
     fn recursive_is_valid(acc: i64, idx: i32, input: &[i64], result: i64) -> bool {
+        RECURSIVE_CALLS.add();
         if idx as usize == input.len() {
             return acc == result;
         }
@@ -33,6 +43,7 @@ impl Calibration {
     }
 
     fn recursive_is_valid_with_concat(acc: i64, idx: i32, input: &[i64], result: i64) -> bool {
+        RECURSIVE_CALLS_WITH_CONCAT.add();
         if idx as usize == input.len() {
             return acc == result;
         }
@@ -56,40 +67,66 @@ impl Calibration {
 
 }
 
+// State is what --dump-state writes, every calibration with whether the operators can produce its result
+#[derive(Serialize)]
+struct State {
+    calibrations: Vec<CalibrationState>,
+    sum: i64,
+    sum_with_concat: i64,
+    stats: Stats,
+}
+
+#[derive(Serialize)]
+struct CalibrationState {
+    calibration: Calibration,
+    valid: bool,
+    valid_with_concat: bool,
+}
+
 fn main() {
     let input = std::env::args().nth(1).expect("Please provide an input");
 
-    let calibrations = parse_input(&input);
-
-    calibrations.iter().for_each(|calibration| {
+    // calibrations are streamed from the file, once for the listing and once per set of operators
+    parse_input(&input).for_each(|calibration| {
         calibration.print();
     });
 
-    let mut filtered = calibrations.iter()
-        .filter(|calibration| (*calibration).is_valid())
-        .collect::<Vec<_>>();
-
     println!("Valid calibrations:");
-    filtered.iter().for_each(|calibration| calibration.print());
-
-    filtered.iter()
-        .map(|c| c.result)
-        .reduce(|a, b| a + b)
-        .map(|sum| println!("Sum: {}", sum));
-
-    filtered = calibrations.iter()
-        .filter(|calibration| (*calibration).is_valid_with_concat())
-        .collect::<Vec<_>>();
+    let mut sum = None;
+    for calibration in parse_input(&input).filter(|calibration| calibration.is_valid()) {
+        calibration.print();
+        sum = Some(sum.unwrap_or(0) + calibration.result);
+    }
+    sum.map(|sum| println!("Sum: {}", sum));
 
     println!("Valid calibrations with concatenation:");
-    filtered.iter().for_each(|calibration| calibration.print());
+    sum = None;
+    for calibration in parse_input(&input).filter(|calibration| calibration.is_valid_with_concat()) {
+        calibration.print();
+        sum = Some(sum.unwrap_or(0) + calibration.result);
+    }
+    sum.map(|sum| println!("Sum: {}", sum));
+    // collected before the state is dumped, which checks every calibration again
+    let stats = Stats::collect(&COUNTERS);
+    if counters::requested() {
+        stats.print();
+    }
 
-    filtered.iter()
-        .map(|c| c.result)
-        .reduce(|a, b| a + b)
-        .map(|sum| println!("Sum: {}", sum));
+    if let Some(path) = std::env::args().skip_while(|arg| arg != "--dump-state").nth(1) {
+        let calibrations: Vec<CalibrationState> = parse_input(&input).map(|calibration| CalibrationState {
+            valid: calibration.is_valid(),
+            valid_with_concat: calibration.is_valid_with_concat(),
+            calibration,
+        }).collect();
+        let sum = calibrations.iter().filter(|c| c.valid).map(|c| c.calibration.result).sum();
+        let sum_with_concat = calibrations.iter().filter(|c| c.valid_with_concat).map(|c| c.calibration.result).sum();
+        let state = State { calibrations, sum, sum_with_concat, stats };
+        serde_json::to_writer_pretty(File::create(&path).expect("Failed to create state file"), &state)
+            .expect("Failed to write state");
+        println!("State dumped to {}", path);
+    }
 }
 
-fn parse_input(filename: &str) -> Vec<Calibration> {
-    read_to_string(filename).unwrap().lines().map(|line| Calibration::parse(line)).collect()
-}
\ No newline at end of file
+fn parse_input(filename: &str) -> impl Iterator<Item = Calibration> {
+    input::lines(filename).map(|line| Calibration::parse(&line))
+}
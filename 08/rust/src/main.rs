@@ -1,6 +1,8 @@
-use std::{fs::read_to_string, collections::{HashMap, HashSet}};
+use std::{fs::File, collections::{BTreeMap, HashMap, HashSet}};
+use serde::Serialize;
+use render::{ansi, palette, Canvas, Rgb};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 struct Antena {
     frequency: char,
     row: usize,
@@ -14,6 +16,17 @@ struct Chart {
     anti_nodes: HashSet<(usize, usize)>,
     anti_nodes_v2: HashSet<(usize, usize)>,
     map: Vec<Vec<char>>,
+    colour: bool,
+}
+
+// State is what --dump-state writes, antenas grouped by frequency and the anti nodes found
+#[derive(Serialize)]
+struct State<'a> {
+    rows: usize,
+    cols: usize,
+    antenas: BTreeMap<char, &'a Vec<Antena>>,
+    anti_nodes: Vec<(usize, usize)>,
+    anti_nodes_v2: Vec<(usize, usize)>,
 }
 
 impl Chart {
@@ -42,12 +55,16 @@ impl Chart {
             anti_nodes,
             map,
             anti_nodes_v2,
+            colour: false,
         }
     }
     
     fn find_anti_nodes(&mut self) {
         let within_boundaries = |row, col| row >= 0 && row < self.rows as isize && col >= 0 && col < self.cols as isize;
-        for frequency in self.antenas.keys() {
+        // frequencies are visited in order so the trace printed is the same on every run
+        let mut frequencies: Vec<&char> = self.antenas.keys().collect();
+        frequencies.sort();
+        for frequency in frequencies {
             let antenas = self.antenas.get(frequency).unwrap();
             for (i, antena) in antenas.iter().enumerate() {
                 for (j, other_antena) in antenas.iter().enumerate() {
@@ -108,12 +125,24 @@ impl Chart {
     fn print_map(&self) {
         for row in self.map.iter() {
             for c in row.iter() {
-                print!("{}", c);
+                print!("{}", self.paint(*c));
             }
             println!();
         }
     }
 
+    // paint colours every antena frequency differently and anti nodes in red
+    fn paint(&self, c: char) -> String {
+        if !self.colour {
+            return c.to_string();
+        }
+        match c {
+            '.' => ansi::fg(c, Rgb::GREY),
+            '#' => ansi::fg(c, Rgb::RED),
+            _ => ansi::fg(c, palette(c as usize)),
+        }
+    }
+
     fn print_with_anti_nodes(&self) {
         let mut map = self.map.clone();
         for (row, col) in self.anti_nodes.iter() {
@@ -123,11 +152,48 @@ impl Chart {
         }
         for row in map.iter() {
             for c in row.iter() {
-                print!("{}", c);
+                print!("{}", self.paint(*c));
             }
             println!();
         }
     }
+
+    // render draws anti nodes v2 in yellow, anti nodes in red (v1 anti nodes are also v2 ones)
+    // and every antena with a colour of its own frequency
+    fn render(&self, filename: &str) {
+        let mut canvas = Canvas::new(self.cols, self.rows, Rgb::WHITE);
+        for (row, col) in self.anti_nodes_v2.iter() {
+            canvas.fill(*row, *col, Rgb::YELLOW);
+        }
+        for (row, col) in self.anti_nodes.iter() {
+            canvas.fill(*row, *col, Rgb::RED);
+        }
+        let mut frequencies: Vec<&char> = self.antenas.keys().collect();
+        frequencies.sort();
+        for (idx, frequency) in frequencies.iter().enumerate() {
+            for antena in self.antenas.get(frequency).unwrap() {
+                canvas.fill(antena.row, antena.col, palette(idx));
+                canvas.label(antena.row, antena.col, antena.frequency);
+            }
+        }
+        canvas.save(filename).expect("Failed to write render");
+    }
+
+    fn dump_state(&self, filename: &str) {
+        let mut anti_nodes: Vec<(usize, usize)> = self.anti_nodes.iter().copied().collect();
+        anti_nodes.sort();
+        let mut anti_nodes_v2: Vec<(usize, usize)> = self.anti_nodes_v2.iter().copied().collect();
+        anti_nodes_v2.sort();
+        let state = State {
+            rows: self.rows,
+            cols: self.cols,
+            antenas: self.antenas.iter().map(|(frequency, antenas)| (*frequency, antenas)).collect(),
+            anti_nodes,
+            anti_nodes_v2,
+        };
+        serde_json::to_writer_pretty(File::create(filename).expect("Failed to create state file"), &state)
+            .expect("Failed to write state");
+    }
 }
 
 impl Antena {
@@ -149,8 +215,8 @@ impl std::fmt::Display for Antena {
 
 fn main() {
     let input = std::env::args().nth(1).expect("Please provide an input");
-    let contents = parse_input(&input);
-    let mut chart = Chart::new(contents.iter().map(|line| line.chars().collect()).collect());
+    let mut chart = Chart::new(parse_input(&input));
+    chart.colour = ansi::enabled(std::env::args().any(|arg| arg == "--color"));
     println!("Map:");
     chart.print_map();
     chart.find_anti_nodes();
@@ -159,13 +225,20 @@ fn main() {
     println!("Total anti nodes: {}", chart.anti_nodes.len());
     println!("Total anti nodes v2: {}", chart.anti_nodes_v2.len());
 
+    if let Some(path) = std::env::args().skip_while(|arg| arg != "--render").nth(1) {
+        chart.render(&path);
+        println!("Rendered to {}", path);
+    }
+    if let Some(path) = std::env::args().skip_while(|arg| arg != "--dump-state").nth(1) {
+        chart.dump_state(&path);
+        println!("State dumped to {}", path);
+    }
+
 }
 
-fn parse_input(filename: &str) -> Vec<String> {
-    read_to_string(filename)
-        .expect("Failed to read file")
-        .trim()
-        .split("\n")
-        .map(|line| line.to_string())
+fn parse_input(filename: &str) -> Vec<Vec<char>> {
+    input::Grid::open(filename)
+        .rows()
+        .map(|row| row.iter().map(|&c| c as char).collect())
         .collect()
 }
@@ -1,13 +1,52 @@
-use std::{env::args, fs::read_to_string, u32};
+use std::{env::args, fs::{read_to_string, File}, u32};
+
+use debugger::{Condition, Debugger, Machine};
+use serde::{Deserialize, Serialize};
+use trace::Recorder;
 
 struct Buffer {
     disk_map: Vec<u32>,
     unsolved: Vec<i32>,
     solved: Vec<i32>,
     file_ids: Vec<i32>,
+    // index in file_ids of the next file fit_algo_b tries to move
+    next_file: usize,
     checksum: u128
 }
 
+// BlockMove is the outcome of trying to move one file in fit_algo_b, it is also the event of a recorded trace
+#[derive(PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum BlockMove {
+    Moved { file_id: i32, from: usize, to: usize, size: usize },
+    Stayed(i32),
+}
+
+impl std::fmt::Display for BlockMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlockMove::Moved { file_id, from, to, size } => write!(f, "file id {} ({} blocks) moved from {} to {}", file_id, size, from, to),
+            BlockMove::Stayed(file_id) => write!(f, "file id {} stayed", file_id),
+        }
+    }
+}
+
+// State is what --dump-state writes, the expanded disk and the layout and checksum of both compactions,
+// free blocks are -1
+#[derive(Serialize)]
+struct State<'a> {
+    disk_map: &'a [u32],
+    unsolved: &'a [i32],
+    part_1: Layout,
+    part_2: Layout,
+}
+
+#[derive(Serialize)]
+struct Layout {
+    solved: Vec<i32>,
+    checksum: u128,
+}
+
 fn hide_free(c: &i32) -> String {
     if *c == -1 {
         return ".".to_string();
@@ -32,7 +71,8 @@ impl Buffer {
             unsolved,
             solved,
             checksum,
-            file_ids
+            file_ids,
+            next_file: 0
         }
     }
 
@@ -93,56 +133,55 @@ impl Buffer {
         }
     }
 
-    fn fit_algo_b(&mut self) {
+    // start_algo_b resets the layout for fit_algo_b, files are then moved one at a time by step_algo_b
+    fn start_algo_b(&mut self) {
         self.reset_solved();
         self.solved = self.unsolved.clone();
-        let calc_block_size_from_idx = |v: &Vec<i32>, idx: usize| {
-            let mut block_size = 0;
-            let c = v[idx];
-            for i in idx..v.len() {
-                if v[idx+(i-idx)] == c {
-                    block_size += 1;
-                } else {
-                    break;
-                }
-            }
-            block_size
-        };
-        let index_of_leftmost_free_space_of_size_at_least = |v: &Vec<i32>, size: usize| {
-            let mut return_idx: i32 = -1;
-            let mut free_size = 0;
-            for (idx, element) in v.iter().enumerate() {
-                if free_size >= size {
-                    return_idx = (idx - size) as i32;
-                    break;
-                }
-                if *element == -1 {
-                    free_size += 1;
-                } else {
-                    free_size = 0;
-                }
+        self.next_file = 0;
+    }
+
+    // step_algo_b tries to move the next file, in decreasing file id order, None once every file was tried
+    fn step_algo_b(&mut self) -> Option<BlockMove> {
+        let i = *self.file_ids.get(self.next_file)?;
+        self.next_file += 1;
+        // we calculate the size of the memory block that we want to store
+        let file_idx = self.solved.iter().position(|&x| x == i).unwrap();
+        let file_size = block_size_from_idx(&self.solved, file_idx);
+        let free_available_idx = index_of_leftmost_free_space_of_size_at_least(&self.solved, file_size);
+        //println!("File id {} has size {}, leftmost available free memory at idx {}", i, file_size, free_available_idx);
+        // if we reach the end of the memory, reduce the idx_end by the block size
+        if free_available_idx == -1 || file_idx < free_available_idx as usize {
+            return Some(BlockMove::Stayed(i));
+        }
+        //println!("Moving file id {} from position {} to free memory at {}", i, file_idx, free_available_idx);
+        // if block size is smaller or equal than free size
+        for k in 0..file_size {
+            //we store the block in the free memory
+            self.solved[free_available_idx as usize + k] = i;
+            // we remove the block from the end
+            self.solved[file_idx + k] = -1;
+        }
+        Some(BlockMove::Moved { file_id: i, from: file_idx, to: free_available_idx as usize, size: file_size })
+    }
+
+    // apply replays a recorded block move of fit_algo_b
+    fn apply(&mut self, block_move: &BlockMove) {
+        if let BlockMove::Moved { file_id, from, to, size } = block_move {
+            for k in 0..*size {
+                self.solved[to + k] = *file_id;
+                self.solved[from + k] = -1;
             }
-            return_idx
-        };
+        }
+        self.next_file += 1;
+    }
 
-        for i in self.file_ids.iter() {
+    fn fit_algo_b(&mut self, mut recorder: Option<&mut Recorder>) {
+        self.start_algo_b();
+        while let Some(i) = self.file_ids.get(self.next_file) {
             println!("Processing file id {}", i);
-            // we calculate the size of the memory block that we want to store
-            let file_idx = self.solved.iter().position(|&x| x == *i).unwrap();
-            let file_size = calc_block_size_from_idx(&self.solved, file_idx);
-            let free_available_idx = index_of_leftmost_free_space_of_size_at_least(&self.solved, file_size);
-            //println!("File id {} has size {}, leftmost available free memory at idx {}", *i, file_size, free_available_idx);
-            // if we reach the end of the memory, reduce the idx_end by the block size
-            if free_available_idx == -1 || file_idx < free_available_idx as usize {
-                continue;
-            }
-            //println!("Moving file id {} from position {} to free memory at {}", *i, file_idx, free_available_idx);
-            // if block size is smaller or equal than free size
-            for k in 0..file_size {
-                //we store the block in the free memory
-                self.solved[free_available_idx as usize + k] = *i;
-                // we remove the block from the end
-                self.solved[file_idx + k] = -1;
+            let block_move = self.step_algo_b().unwrap();
+            if let Some(recorder) = recorder.as_mut() {
+                recorder.record(&block_move);
             }
             //self.print_solved();
         }
@@ -160,19 +199,139 @@ impl Buffer {
     }
 }
 
+fn block_size_from_idx(v: &[i32], idx: usize) -> usize {
+    v[idx..].iter().take_while(|&&x| x == v[idx]).count()
+}
+
+fn index_of_leftmost_free_space_of_size_at_least(v: &[i32], size: usize) -> i32 {
+    let mut return_idx: i32 = -1;
+    let mut free_size = 0;
+    for (idx, element) in v.iter().enumerate() {
+        if free_size >= size {
+            return_idx = (idx - size) as i32;
+            break;
+        }
+        if *element == -1 {
+            free_size += 1;
+        } else {
+            free_size = 0;
+        }
+    }
+    return_idx
+}
+
+impl Machine for Buffer {
+    type Snapshot = (Vec<i32>, usize);
+    type Event = BlockMove;
+
+    const CONDITIONS: &'static str = "    moved <file id>  the file is moved
+    file <file id>   the file is tried, whether it moves or not
+    moves            any file is moved";
+
+    fn step(&mut self) -> Option<BlockMove> {
+        self.step_algo_b()
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        (self.solved.clone(), self.next_file)
+    }
+
+    fn restore(&mut self, snapshot: &Self::Snapshot) {
+        self.solved = snapshot.0.clone();
+        self.next_file = snapshot.1;
+    }
+
+    fn show(&self) {
+        self.print_solved();
+        match self.file_ids.get(self.next_file) {
+            Some(i) => println!("Next file id: {}", i),
+            None => println!("Every file was tried"),
+        }
+    }
+
+    fn condition(words: &[&str]) -> Option<Condition<Self>> {
+        match words {
+            ["moved", id] => {
+                let id: i32 = id.parse().ok()?;
+                Some(Box::new(move |_, event| matches!(event, BlockMove::Moved { file_id, .. } if *file_id == id)))
+            }
+            ["file", id] => {
+                let id: i32 = id.parse().ok()?;
+                Some(Box::new(move |_, event| match event {
+                    BlockMove::Moved { file_id, .. } | BlockMove::Stayed(file_id) => *file_id == id,
+                }))
+            }
+            ["moves"] => Some(Box::new(|_, event| matches!(event, BlockMove::Moved { .. }))),
+            _ => None,
+        }
+    }
+}
+
+// replay rebuilds the disk after the first --at block moves of a trace, the trace starts from the disk map.
+// With --check the compaction is simulated alongside and must move the same blocks.
+fn replay(path: &str) {
+    let (disk_map, events) = trace::open::<String, BlockMove>(path, 9);
+    let start = |disk_map: &str| {
+        let mut buffer = Buffer::new(disk_map.to_string());
+        buffer.expand();
+        buffer.start_algo_b();
+        buffer
+    };
+    let mut buffer = start(&disk_map);
+    let mut simulation = trace::check().then(|| start(&disk_map));
+    let mut replayed = 0;
+    for event in events.take(trace::at()) {
+        if let Some(simulation) = simulation.as_mut() {
+            let block_move = simulation.step_algo_b();
+            if block_move.as_ref() != Some(&event) {
+                let simulated = block_move.map_or("nothing".to_string(), |block_move| block_move.to_string());
+                println!("Move {} differs, recorded {} but simulated {}", replayed + 1, event, simulated);
+                std::process::exit(1);
+            }
+        }
+        buffer.apply(&event);
+        replayed += 1;
+    }
+    buffer.print_solved();
+    println!("Replayed {} moves", replayed);
+}
+
 fn main() {
+    if let Some(path) = trace::replay() {
+        replay(&path);
+        return;
+    }
     let filename = args().nth(1).expect("Please provide an input");
     let mut buffer = Buffer::new(read_to_string(filename).expect("Failed to read the file"));
     buffer.expand();
+    if args().any(|arg| arg == "--debug") {
+        buffer.start_algo_b();
+        Debugger::new(buffer).run();
+        return;
+    }
     println!("Part 1");
     buffer.print_unsolved();
     buffer.fit_algo_a();
     buffer.calculate_checksum();
     buffer.print();
+    let part_1 = Layout { solved: buffer.solved.clone(), checksum: buffer.checksum };
     println!("\n\n\n\n");
     println!("Part 2");
     buffer.print_unsolved();
-    buffer.fit_algo_b();
+    let disk_map: String = buffer.disk_map.iter().map(|c| c.to_string()).collect();
+    buffer.fit_algo_b(trace::recorder(9, &disk_map).as_mut());
     buffer.calculate_checksum();
     buffer.print();
+
+    if let Some(path) = args().skip_while(|arg| arg != "--dump-state").nth(1) {
+        let state = State {
+            disk_map: &buffer.disk_map,
+            unsolved: &buffer.unsolved,
+            part_1,
+            part_2: Layout { solved: buffer.solved.clone(), checksum: buffer.checksum },
+        };
+        serde_json::to_writer_pretty(File::create(&path).expect("Failed to create state file"), &state)
+            .expect("Failed to write state");
+        println!("State dumped to {}", path);
+    }
 }
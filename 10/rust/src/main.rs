@@ -1,6 +1,14 @@
-use std::{env::args, fs::read_to_string, fmt};
+use std::{env::args, fs::File, fmt};
+use counters::{Counter, Stats};
+use serde::Serialize;
+use render::{ansi, gradient, Canvas, Rgb};
 
-#[derive(Clone)]
+// recursive calls made to score and rate the trailheads, reported with --stats and in --dump-state
+static SCORE_CALLS: Counter = Counter::new("score_calls", "Recursive calls to calc_score");
+static RATING_CALLS: Counter = Counter::new("rating_calls", "Recursive calls to calc_rating");
+static COUNTERS: [&Counter; 2] = [&SCORE_CALLS, &RATING_CALLS];
+
+#[derive(Clone, Serialize)]
 struct Trailhead {
     start: Coordinate,
     score: usize,
@@ -27,6 +35,7 @@ impl Trailhead {
     }
 
     fn calc_score(&mut self, start: &Coordinate, map: &Map, history: Vec<Coordinate>) {
+        SCORE_CALLS.add();
         let reachables: Vec<Coordinate> = self.find_reachables(start, map.height, map.width);
         let me = map.input[start.y][start.x].to_digit(10).unwrap() as i32;
         for pos in reachables.iter() {
@@ -49,6 +58,7 @@ impl Trailhead {
     }
 
     fn calc_rating(&mut self, start: &Coordinate, map: &Map, history: Vec<Coordinate>) {
+        RATING_CALLS.add();
         let reachables: Vec<Coordinate> = self.find_reachables(start, map.height, map.width);
         let me = map.input[start.y][start.x].to_digit(10).unwrap() as i32;
         for pos in reachables.iter() {
@@ -83,7 +93,7 @@ impl Trailhead {
 
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 struct Coordinate {
     x: usize,
     y: usize,
@@ -107,6 +117,17 @@ impl Coordinate {
     }
 }
 
+// State is what --dump-state writes, every trailhead with the peaks and routes it reaches
+#[derive(Serialize)]
+struct State<'a> {
+    width: usize,
+    height: usize,
+    trailheads: &'a [Trailhead],
+    total_score: usize,
+    total_rating: usize,
+    stats: Stats,
+}
+
 struct Map {
     input: Vec<Vec<char>>,
     width: usize,
@@ -114,6 +135,7 @@ struct Map {
     trailheads: Vec<Trailhead>,
     scores: Vec<usize>,
     ratings: Vec<usize>,
+    colour: bool,
 }
 
 impl Map {
@@ -129,7 +151,8 @@ impl Map {
             height,
             trailheads,
             scores,
-            ratings
+            ratings,
+            colour: false,
         }
     }
 
@@ -143,7 +166,15 @@ impl Map {
     fn print_map(&self) {
         println!("Map:");
         for row in self.input.iter() {
-            println!("{}", row.iter().collect::<String>());
+            if self.colour {
+                // heights go from blue (0) to red (9)
+                println!("{}", row.iter().map(|c| {
+                    let height = c.to_digit(10).unwrap_or(0) as f64;
+                    ansi::fg(c, gradient(Rgb::BLUE, Rgb::RED, height / 9.0))
+                }).collect::<String>());
+            } else {
+                println!("{}", row.iter().collect::<String>());
+            }
         }
     }
 
@@ -153,6 +184,40 @@ impl Map {
         }
     }
 
+    // render draws heights in shades of grey, and the cells of the trails found for every
+    // trailhead going from yellow (height 0) to red (height 9)
+    fn render(&self, filename: &str) {
+        let mut canvas = Canvas::new(self.width, self.height, Rgb::WHITE);
+        for (y, row) in self.input.iter().enumerate() {
+            for (x, c) in row.iter().enumerate() {
+                if let Some(height) = c.to_digit(10) {
+                    canvas.fill(y, x, gradient(Rgb::WHITE, Rgb::GREY, height as f64 / 9.0));
+                }
+                canvas.label(y, x, *c);
+            }
+        }
+        for trailhead in self.trailheads.iter() {
+            for coord in trailhead.routes.iter().flatten() {
+                let height = self.input[coord.y][coord.x].to_digit(10).unwrap();
+                canvas.fill(coord.y, coord.x, gradient(Rgb::YELLOW, Rgb::RED, height as f64 / 9.0));
+            }
+        }
+        canvas.save(filename).expect("Failed to write render");
+    }
+
+    fn dump_state(&self, filename: &str) {
+        let state = State {
+            width: self.width,
+            height: self.height,
+            trailheads: &self.trailheads,
+            total_score: self.scores.iter().sum(),
+            total_rating: self.ratings.iter().sum(),
+            stats: Stats::collect(&COUNTERS),
+        };
+        serde_json::to_writer_pretty(File::create(filename).expect("Failed to create state file"), &state)
+            .expect("Failed to write state");
+    }
+
     fn find_zeros(&mut self) {
         self.trailheads.clear();
         let mut zeros: Vec<Trailhead> = Vec::new();
@@ -185,16 +250,28 @@ impl Map {
 fn main() {
     let filename = args().nth(1).expect("Input file name missing");
     let mut map = read_map(&filename);
+    map.colour = ansi::enabled(args().any(|arg| arg == "--color"));
     map.find_zeros();
     map.find_trails();
     map.print();
+    if counters::requested() {
+        Stats::collect(&COUNTERS).print();
+    }
+
+    if let Some(path) = args().skip_while(|arg| arg != "--render").nth(1) {
+        map.render(&path);
+        println!("Rendered to {}", path);
+    }
+    if let Some(path) = args().skip_while(|arg| arg != "--dump-state").nth(1) {
+        map.dump_state(&path);
+        println!("State dumped to {}", path);
+    }
 }
 
 fn read_map(filename: &str) -> Map {
-    let input = read_to_string(filename)
-        .expect("Failed to read file")
-        .lines()
-        .map(|line| line.chars().collect())
+    let input = input::Grid::open(filename)
+        .rows()
+        .map(|row| row.iter().map(|&c| c as char).collect())
         .collect();
     Map::new(input)
 }
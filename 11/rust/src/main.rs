@@ -1,4 +1,13 @@
-use std::{collections::HashMap, fmt::{Debug, Display, Formatter, Result}};
+use std::{collections::HashMap, fmt::{Debug, Display, Formatter, Result}, fs::File};
+
+use counters::{Counter, Stats};
+use progress::Progress;
+use serde::Serialize;
+
+// lookups of the recursive_blink cache, reported with --stats and in --dump-state
+static CACHE_HITS: Counter = Counter::new("cache_hits", "Cache hits");
+static CACHE_MISSES: Counter = Counter::new("cache_misses", "Cache misses");
+static COUNTERS: [&Counter; 2] = [&CACHE_HITS, &CACHE_MISSES];
 
 #[derive(Eq, PartialEq)]
 #[derive(Hash)]
@@ -41,9 +50,24 @@ impl Rock {
     }
 }
 
+// State is what --dump-state writes, how many rocks each initial rock becomes after the blinks
+#[derive(Serialize)]
+struct State {
+    blinks: usize,
+    rocks: Vec<RockState>,
+    total: usize,
+    cached_pairs: usize,
+    stats: Stats,
+}
+
+#[derive(Serialize)]
+struct RockState {
+    value: usize,
+    count: usize,
+}
+
 fn main() {
     let filename = std::env::args().nth(1).expect("Filename required");
-    let blinks: usize = std::env::args().nth(2).expect("Blinks required").parse().unwrap();
     let rocks: Vec<Rock> = std::fs::read_to_string(filename)
         .expect("Can't read file")
         .lines()
@@ -51,23 +75,87 @@ fn main() {
         .flatten()
         .map(|rock| Rock::new(rock.to_string().parse().unwrap()))
         .collect::<Vec<Rock>>();
+    if std::env::args().any(|arg| arg == "--repl") {
+        repl(&rocks);
+        return;
+    }
+    let blinks: usize = std::env::args().nth(2).expect("Blinks required").parse().unwrap();
 
     println!("Initial arrangement: ");
     println!("{:?}", rocks);
     let mut cache = HashMap::new();
+    // blinks are counted one depth at a time, the cache makes each depth cheap once the previous one is known
+    // and the total of the last finished depth is the partial result when time runs out
+    // --stats counts the cache lookups of every depth, this is where the blinking is done
+    let mut progress = Progress::new("Blinking", blinks as u64).timeout(progress::timeout());
+    for depth in 1..=blinks {
+        if !progress.step() {
+            let total: usize = rocks.iter().map(|rock| recursive_blink(rock.clone(), depth - 1, &mut cache)).sum();
+            println!("Timed out after {:.1}s, blinked {} of {} times", progress.elapsed().as_secs_f64(), depth - 1, blinks);
+            println!("Total so far: {}", total);
+            std::process::exit(progress::TIMED_OUT);
+        }
+        for rock in rocks.iter() {
+            recursive_blink(rock.clone(), depth, &mut cache);
+        }
+    }
+    progress.finish();
     let mut total = 0;
+    let mut counts = Vec::new();
     for rock in rocks.iter() {
-        total += recursive_blink(rock.clone(), blinks, &mut cache);
+        let count = recursive_blink(rock.clone(), blinks, &mut cache);
+        counts.push(RockState { value: rock.int_val, count });
+        total += count;
     }
     println!("Total: {}", total);
+    if counters::requested() {
+        Stats::collect(&COUNTERS).print();
+    }
+
+    if let Some(path) = std::env::args().skip_while(|arg| arg != "--dump-state").nth(1) {
+        let state = State { blinks, rocks: counts, total, cached_pairs: cache.len(), stats: Stats::collect(&COUNTERS) };
+        serde_json::to_writer_pretty(File::create(&path).expect("Failed to create state file"), &state)
+            .expect("Failed to write state");
+        println!("State dumped to {}", path);
+    }
+}
+
+// repl answers queries about the rocks until quit or end of input,
+// the cache is kept between queries so deep blinks get cheaper as you go
+fn repl(rocks: &[Rock]) {
+    let help = "Commands:
+  rocks                  initial arrangement
+  blink <value> <depth>  number of rocks a single rock becomes after depth blinks
+  total <depth>          number of rocks the initial arrangement becomes after depth blinks
+  cache                  number of cached (value, depth) pairs
+  quit";
+    println!("{} rocks loaded, type help for commands", rocks.len());
+    repl::run(&mut HashMap::new(), help, |_| "> ".to_string(), |cache, words| {
+        match words {
+            ["rocks"] => println!("{:?}", rocks),
+            ["blink", value, depth] => match (value.parse::<usize>(), depth.parse::<usize>()) {
+                (Ok(value), Ok(depth)) => println!("{}", recursive_blink(Rock::new(value), depth, cache)),
+                _ => println!("Invalid value or depth"),
+            },
+            ["total", depth] => match depth.parse::<usize>() {
+                Ok(depth) => println!("{}", rocks.iter().map(|rock| recursive_blink(rock.clone(), depth, cache)).sum::<usize>()),
+                Err(_) => println!("Invalid depth"),
+            },
+            ["cache"] => println!("{}", cache.len()),
+            _ => return false,
+        }
+        true
+    });
 }
 
 fn recursive_blink(rock: Rock, depth: usize, cache: &mut HashMap<Pair, usize>) -> usize {
     let key = Pair::new(rock.int_val, depth);
     // if element is in cache, return it
     if cache.get(&key).is_some() {
+        CACHE_HITS.add();
         return cache.get(&key).unwrap().clone();
     }
+    CACHE_MISSES.add();
     // if depth is 0, return 1
     if depth == 0 {
         return 1;
@@ -1,4 +1,13 @@
+use std::fs::File;
+
 use itertools::Itertools;
+use render::{ansi, palette, Canvas, Edge, Rgb};
+use counters::{Counter, Stats};
+use serde::Serialize;
+
+// SIDE_COMPARISONS counts the pairs of sides compared by group_sides, reported with --stats and in --dump-state
+static SIDE_COMPARISONS: Counter = Counter::new("side_comparisons", "Side comparisons in group_sides");
+static COUNTERS: [&Counter; 1] = [&SIDE_COMPARISONS];
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 enum Direction {
@@ -19,7 +28,7 @@ impl std::fmt::Debug for Direction {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize)]
 struct Cell {
     row: usize,
     col: usize,
@@ -78,6 +87,7 @@ impl<'a> std::fmt::Debug for Side<'a> {
 
 impl<'a> Side<'a> {
     fn continuation(&self, other: &Self) -> bool {
+        SIDE_COMPARISONS.add();
         let same_dir = self.dir == other.dir;
         let same_col = self.cell.col == other.cell.col;
         let same_row = self.cell.row == other.cell.row;
@@ -97,6 +107,7 @@ impl<'a> Side<'a> {
     }
 
     fn same_border(&self, other: &Self) -> bool {
+        SIDE_COMPARISONS.add();
         let same_col = self.cell.col == other.cell.col;
         let same_row = self.cell.row == other.cell.row;
         let west_east_neighbor = self.dir == Direction::East
@@ -122,6 +133,7 @@ impl<'a> Side<'a> {
     }
 }
 
+#[derive(Serialize)]
 struct Plot {
     perimeter: u32,
     area: u32,
@@ -146,12 +158,24 @@ impl Plot {
     }
 }
 
+// State is what --dump-state writes, every plot with its cells and measures, and the garden values
+#[derive(Serialize)]
+struct State<'a> {
+    width: usize,
+    height: usize,
+    plots: &'a [Plot],
+    value_by_perimeter: u32,
+    value_by_sides: u32,
+    stats: Stats,
+}
+
 struct Garden {
     input: Vec<Vec<char>>,
     visited: Vec<Vec<bool>>,
     plots: Vec<Plot>,
     width: usize,
     height: usize,
+    colour: bool,
 }
 
 impl std::fmt::Display for Garden {
@@ -159,7 +183,11 @@ impl std::fmt::Display for Garden {
         writeln!(f, "Garden:")?;
         for row in &self.input {
             for cell in row {
-                write!(f, "{}", cell)?;
+                if self.colour {
+                    write!(f, "{}", ansi::bg(cell, palette(*cell as usize)))?;
+                } else {
+                    write!(f, "{}", cell)?;
+                }
             }
             writeln!(f)?;
         }
@@ -196,6 +224,7 @@ impl Garden {
             plots,
             width,
             height,
+            colour: false,
         }
     }
 
@@ -280,17 +309,71 @@ impl Garden {
         println!();
         return sides.len() as u32;
     }
+
+    fn dump_state(&self, filename: &str) {
+        let state = State {
+            width: self.width,
+            height: self.height,
+            plots: &self.plots,
+            value_by_perimeter: self.plots.iter().map(|plot| plot.value()).sum(),
+            value_by_sides: self.plots.iter().map(|plot| plot.area * plot.sides).sum(),
+            stats: Stats::collect(&COUNTERS),
+        };
+        serde_json::to_writer_pretty(File::create(filename).expect("Failed to create state file"), &state)
+            .expect("Failed to write state");
+    }
+
+    // render draws every plot in a colour of its own, with fences between neighbouring plots
+    fn render(&self, filename: &str) {
+        let mut canvas = Canvas::new(self.width, self.height, Rgb::WHITE);
+        let fenced = |row: i32, col: i32, symbol: char| {
+            row < 0 || row as usize >= self.height || col < 0 || col as usize >= self.width
+                || self.input[row as usize][col as usize] != symbol
+        };
+        for (idx, plot) in self.plots.iter().enumerate() {
+            for cell in plot.cells.iter() {
+                canvas.fill(cell.row, cell.col, palette(idx));
+                canvas.label(cell.row, cell.col, plot.symbol);
+                let (row, col) = (cell.row as i32, cell.col as i32);
+                if fenced(row - 1, col, plot.symbol) {
+                    canvas.wall(cell.row, cell.col, Edge::North);
+                }
+                if fenced(row, col + 1, plot.symbol) {
+                    canvas.wall(cell.row, cell.col, Edge::East);
+                }
+                if fenced(row + 1, col, plot.symbol) {
+                    canvas.wall(cell.row, cell.col, Edge::South);
+                }
+                if fenced(row, col - 1, plot.symbol) {
+                    canvas.wall(cell.row, cell.col, Edge::West);
+                }
+            }
+        }
+        canvas.save(filename).expect("Failed to write render");
+    }
 }
 
 fn main() {
     let filename = std::env::args().nth(1).expect("Filename required");
     let mut garden: Garden = Garden::new(
-        std::fs::read_to_string(filename)
-            .expect("Failed to read file")
-            .lines()
-            .map(|line| line.chars().collect())
+        input::Grid::open(filename)
+            .rows()
+            .map(|row| row.iter().map(|&c| c as char).collect())
             .collect(),
     );
+    garden.colour = ansi::enabled(std::env::args().any(|arg| arg == "--color"));
     garden.estimate_plots();
     println!("{}", garden);
+    if counters::requested() {
+        Stats::collect(&COUNTERS).print();
+    }
+
+    if let Some(path) = std::env::args().skip_while(|arg| arg != "--render").nth(1) {
+        garden.render(&path);
+        println!("Rendered to {}", path);
+    }
+    if let Some(path) = std::env::args().skip_while(|arg| arg != "--dump-state").nth(1) {
+        garden.dump_state(&path);
+        println!("State dumped to {}", path);
+    }
 }
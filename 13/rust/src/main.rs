@@ -1,3 +1,8 @@
+use std::fs::File;
+
+use serde::Serialize;
+
+#[derive(Serialize)]
 struct Point {
     x: f64,
     y: f64,
@@ -12,6 +17,7 @@ impl Point {
     }
 }
 
+#[derive(Serialize)]
 struct Button {
     name: String,
     point: Point,
@@ -44,6 +50,7 @@ impl From<String> for Button {
     }
 }
 
+#[derive(Serialize)]
 struct ClawMachine {
     button_a: Button,
     button_b: Button,
@@ -56,6 +63,30 @@ impl std::fmt::Display for ClawMachine {
     }
 }
 
+// Presses is how many times each button has to be pushed to reach the prize
+#[derive(Serialize)]
+struct Presses {
+    button_a: u64,
+    button_b: u64,
+    tokens: u64,
+}
+
+// State is what --dump-state writes, every claw machine with the presses that win its prize, if any
+#[derive(Serialize)]
+struct State {
+    button_a_cost: f64,
+    button_b_cost: f64,
+    shift: f64,
+    machines: Vec<MachineState>,
+    tokens: u64,
+}
+
+#[derive(Serialize)]
+struct MachineState {
+    machine: ClawMachine,
+    presses: Option<Presses>,
+}
+
 impl ClawMachine {
     fn new(button_a: Button, button_b: Button, prize: Point) -> Self {
         Self {
@@ -71,61 +102,83 @@ fn main() {
     let buton_a_cost = std::env::args().nth(2).expect("No button A cost provided").parse::<f64>().unwrap();
     let buton_b_cost = std::env::args().nth(3).expect("No button B cost provided").parse::<f64>().unwrap();
     let shift: f64 = std::env::args().nth(4).expect("No shift provided").parse::<f64>().unwrap();
-    let contents = std::fs::read_to_string(filename)
-        .expect("Cannot read file")
-        .lines()
-        .map(|x| x.to_string())
-        .filter(|x| x.len() > 0)
-        .collect::<Vec<String>>();
-    let mut i = 0;
-    let mut claw_machines: Vec<ClawMachine> = Vec::new();
-    while i < contents.len() {
-        let button_a = Button::from(contents.get(i).unwrap().to_string());
-        let button_b = Button::from(contents.get(i + 1).unwrap().to_string());
-        let prize_coordinates = contents.get(i+2).unwrap().split(": ").collect::<Vec<&str>>()[1].split(", ").collect::<Vec<&str>>();
-        let prize = Point::new(
-            prize_coordinates[0].replace("X=", "").parse::<f64>().unwrap()+shift, 
-            prize_coordinates[1].replace("Y=", "").parse::<f64>().unwrap()+shift);
-        let claw_machine = ClawMachine::new(button_a, button_b, prize);
-        claw_machines.push(claw_machine);
-        i += 3
-    }
-    for (idx, claw_machine) in claw_machines.iter().clone().enumerate() {
+    // claw machines are streamed from the file, once to list them and once to solve them,
+    // and only kept when the state is dumped
+    for (idx, claw_machine) in claw_machines(&filename, shift).enumerate() {
         println!("{}: {}\n", idx, claw_machine);
     }
 
     println!("Button A cost: {}", buton_a_cost);
     println!("Button B cost: {}", buton_b_cost);
-    let tokens = calculate_tokens(claw_machines, buton_a_cost, buton_b_cost);
+    let dump = std::env::args().skip_while(|arg| arg != "--dump-state").nth(1);
+    let mut machines = Vec::new();
+    let mut tokens: u64 = 0;
+    for (idx, claw_machine) in claw_machines(&filename, shift).enumerate() {
+        let presses = calculate_presses(idx, &claw_machine, buton_a_cost, buton_b_cost);
+        tokens += presses.as_ref().map_or(0, |presses| presses.tokens);
+        if dump.is_some() {
+            machines.push(MachineState { machine: claw_machine, presses });
+        }
+    }
     println!("Tokens: {}", tokens);
+
+    if let Some(path) = dump {
+        let state = State {
+            button_a_cost: buton_a_cost,
+            button_b_cost: buton_b_cost,
+            shift,
+            machines,
+            tokens,
+        };
+        serde_json::to_writer_pretty(File::create(&path).expect("Failed to create state file"), &state)
+            .expect("Failed to write state");
+        println!("State dumped to {}", path);
+    }
 }
 
-fn calculate_tokens(claw_machines: Vec<ClawMachine>, button_a_cost: f64, button_b_cost: f64) -> u64 {
-    let mut tokens: u64 = 0;
+// claw_machines reads the machines three lines at a time, blank lines between them are skipped
+fn claw_machines(filename: &str, shift: f64) -> impl Iterator<Item = ClawMachine> {
+    let mut contents = input::lines(filename).filter(|x| x.len() > 0);
+    std::iter::from_fn(move || {
+        let button_a = Button::from(contents.next()?);
+        let button_b = Button::from(contents.next().unwrap());
+        let prize_line = contents.next().unwrap();
+        let prize_coordinates = prize_line.split(": ").collect::<Vec<&str>>()[1].split(", ").collect::<Vec<&str>>();
+        let prize = Point::new(
+            prize_coordinates[0].replace("X=", "").parse::<f64>().unwrap()+shift, 
+            prize_coordinates[1].replace("Y=", "").parse::<f64>().unwrap()+shift);
+        Some(ClawMachine::new(button_a, button_b, prize))
+    })
+}
+
+// calculate_presses returns how many times each button of the claw machine has to be pushed to win its prize,
+// None when the prize can't be won
+fn calculate_presses(idx: usize, claw_machine: &ClawMachine, button_a_cost: f64, button_b_cost: f64) -> Option<Presses> {
     // solve using linear algebra
-    for (idx, claw_machine) in claw_machines.iter().clone().enumerate() {
-        let ax: f64 = claw_machine.button_a.point.x;
-        let ay: f64 = claw_machine.button_a.point.y;
-        let bx: f64 = claw_machine.button_b.point.x;
-        let by: f64 = claw_machine.button_b.point.y;
-        let px: f64 = claw_machine.prize.x;
-        let py: f64 = claw_machine.prize.y;
-        // ax * i + bx * j = px
-        // ay * i + by * j = py
-        // solve for i and j
-        let det: f64 = (ax * by - ay * bx) as f64;
-        if det == 0.0 {
-            println!("No solution for claw machine: {}", idx);
-            continue;
-        }
-        let i: f64 = (px * by - py * bx) as f64 / det;
-        let j: f64 = (px as f64 - ax as f64 * i) / bx as f64;
-        if i.rem_euclid(1.0) != 0.0 || j.rem_euclid(1.0) != 0.0 {// || i > 100.0 || j > 100.0 {
-            println!("No integer solution for claw machine: {}", idx);
-            continue;
-        }
-        tokens += i as u64 * button_a_cost as u64 + j as u64 * button_b_cost as u64;
-        println!("Claw machine {}: A={} times, B={} times, tokens={}", idx, i, j, i as u64 * button_a_cost as u64 + j as u64 * button_b_cost as u64);
+    let ax: f64 = claw_machine.button_a.point.x;
+    let ay: f64 = claw_machine.button_a.point.y;
+    let bx: f64 = claw_machine.button_b.point.x;
+    let by: f64 = claw_machine.button_b.point.y;
+    let px: f64 = claw_machine.prize.x;
+    let py: f64 = claw_machine.prize.y;
+    // ax * i + bx * j = px
+    // ay * i + by * j = py
+    // solve for i and j
+    let det: f64 = (ax * by - ay * bx) as f64;
+    if det == 0.0 {
+        println!("No solution for claw machine: {}", idx);
+        return None;
+    }
+    let i: f64 = (px * by - py * bx) as f64 / det;
+    let j: f64 = (px as f64 - ax as f64 * i) / bx as f64;
+    if i.rem_euclid(1.0) != 0.0 || j.rem_euclid(1.0) != 0.0 {// || i > 100.0 || j > 100.0 {
+        println!("No integer solution for claw machine: {}", idx);
+        return None;
     }
-    tokens
+    println!("Claw machine {}: A={} times, B={} times, tokens={}", idx, i, j, i as u64 * button_a_cost as u64 + j as u64 * button_b_cost as u64);
+    Some(Presses {
+        button_a: i as u64,
+        button_b: j as u64,
+        tokens: i as u64 * button_a_cost as u64 + j as u64 * button_b_cost as u64,
+    })
 }
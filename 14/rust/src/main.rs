@@ -1,6 +1,10 @@
-use std::clone::{self, Clone};
+use std::{clone::{self, Clone}, fs::File};
+use debugger::{Condition, Debugger, Machine};
+use progress::Progress;
+use render::{ansi, gradient, Canvas, Rgb};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 struct Robot {
     x: u32,
     y: u32,
@@ -28,11 +32,40 @@ impl From<&str> for Robot {
     }
 }
 
+// State is what --dump-state writes, the robots as they are after the given number of seconds
+#[derive(Serialize)]
+struct State<'a> {
+    width: u32,
+    height: u32,
+    second: u32,
+    robots: &'a [Robot],
+    safety_factor: u32,
+    density: u32,
+}
+
+// Start is the first line of a recorded trace, the room the robots start from
+#[derive(Serialize, Deserialize)]
+struct Start {
+    width: u32,
+    height: u32,
+    robots: Vec<Robot>,
+}
+
+// Ticked is the event of a recorded trace, a second went by. Robots move in straight lines,
+// so their positions after any second follow from where they started, and the trace only keeps
+// a fingerprint of the positions the run had, for the replay to check against
+#[derive(PartialEq, Serialize, Deserialize)]
+struct Ticked {
+    second: u32,
+    positions: u64,
+}
+
 #[derive(Clone)]
 struct Room {
     width: u32,
     height: u32,
     robots: Vec<Robot>,
+    colour: bool,
 }
 
 impl std::fmt::Display for Room {
@@ -43,8 +76,12 @@ impl std::fmt::Display for Room {
         }
         for row in grid {
             for cell in row {
-                if cell == 0 {
+                if cell == 0 && self.colour {
+                    write!(f, "{}", ansi::fg('.', Rgb::GREY))?;
+                } else if cell == 0 {
                     write!(f, ".")?;
+                } else if self.colour {
+                    write!(f, "{}", ansi::bg(cell, Rgb::GREEN))?;
                 } else {
                     write!(f, "{}", cell)?;
                 }
@@ -82,6 +119,7 @@ impl Room {
             width,
             height,
             robots,
+            colour: false,
         }
     }
 
@@ -94,7 +132,28 @@ impl Room {
         }
     }
 
-    fn safety_factor(&mut self) -> u32 {
+    // fingerprint hashes the positions of the robots in order with FNV-1a, which unlike the std hasher
+    // gives the same value on every build, so traces can be replayed by another version
+    fn fingerprint(&self) -> u64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for robot in &self.robots {
+            for byte in robot.x.to_le_bytes().into_iter().chain(robot.y.to_le_bytes()) {
+                hash = (hash ^ byte as u64).wrapping_mul(0x100000001b3);
+            }
+        }
+        hash
+    }
+
+    // apply replays a recorded second, moving the robots from where they started to p + v·t mod size
+    fn apply(&mut self, start: &[Robot], ticked: &Ticked) {
+        let t = ticked.second as i64;
+        for (robot, started) in self.robots.iter_mut().zip(start.iter()) {
+            robot.x = (started.x as i64 + started.v_x as i64 * t).rem_euclid(self.width as i64) as u32;
+            robot.y = (started.y as i64 + started.v_y as i64 * t).rem_euclid(self.height as i64) as u32;
+        }
+    }
+
+    fn safety_factor(&self) -> u32 {
         let omitted_x = self.width / 2;
         let omitted_y = self.height / 2;
         let mut area_a = 0;
@@ -122,29 +181,182 @@ impl Room {
         }
         return grid.iter().map(|row| row.iter().filter(|&&cell| cell).count()).fold(0, |acc, x| acc + x as u32);
     }
+
+    fn dump_state(&self, second: u32, filename: &str) {
+        let state = State {
+            width: self.width,
+            height: self.height,
+            second,
+            robots: &self.robots,
+            safety_factor: self.safety_factor(),
+            density: self.density(),
+        };
+        serde_json::to_writer_pretty(File::create(filename).expect("Failed to create state file"), &state)
+            .expect("Failed to write state");
+    }
+
+    // render draws every tile with robots on it in green, darker the more robots share the tile
+    fn render(&self, filename: &str) {
+        let mut grid: Vec<Vec<u32>> = vec![vec![0; self.width as usize]; self.height as usize];
+        for robot in &self.robots {
+            grid[robot.y as usize][robot.x as usize] += 1;
+        }
+        let max = grid.iter().flatten().max().copied().unwrap_or(0).max(1);
+        let mut canvas = Canvas::new(self.width as usize, self.height as usize, Rgb::WHITE);
+        for (y, row) in grid.iter().enumerate() {
+            for (x, count) in row.iter().enumerate() {
+                if *count > 0 {
+                    canvas.fill(y, x, gradient(Rgb::GREEN, Rgb::BLACK, (*count - 1) as f64 / max as f64));
+                }
+            }
+        }
+        canvas.save(filename).expect("Failed to write render");
+    }
+}
+
+// Simulation moves the robots one second per step, for as many seconds as the solver searches
+// (width * height, after which the robots are back where they started)
+struct Simulation {
+    room: Room,
+    second: u32,
+}
+
+// Tick is a step of the simulation, with the density of the room after it
+struct Tick {
+    second: u32,
+    density: u32,
+}
+
+impl std::fmt::Display for Tick {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "second {}, density {}", self.second, self.density)
+    }
+}
+
+impl Machine for Simulation {
+    type Snapshot = (Vec<Robot>, u32);
+    type Event = Tick;
+
+    const CONDITIONS: &'static str = "    density <n>  more than n tiles have a robot on them
+    safety <n>   the safety factor drops below n";
+
+    fn step(&mut self) -> Option<Tick> {
+        if self.second == self.room.width * self.room.height {
+            return None;
+        }
+        self.room.tick();
+        self.second += 1;
+        Some(Tick { second: self.second, density: self.room.density() })
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        (self.room.robots.clone(), self.second)
+    }
+
+    fn restore(&mut self, snapshot: &Self::Snapshot) {
+        self.room.robots = snapshot.0.clone();
+        self.second = snapshot.1;
+    }
+
+    fn show(&self) {
+        print!("{}", self.room);
+        println!("Second: {}, density: {}, safety factor: {}", self.second, self.room.density(), self.room.safety_factor());
+    }
+
+    fn condition(words: &[&str]) -> Option<Condition<Self>> {
+        match words {
+            ["density", n] => {
+                let n: u32 = n.parse().ok()?;
+                Some(Box::new(move |_, tick| tick.density > n))
+            }
+            ["safety", n] => {
+                let n: u32 = n.parse().ok()?;
+                Some(Box::new(move |simulation: &Simulation, _| simulation.room.safety_factor() < n))
+            }
+            _ => None,
+        }
+    }
+}
+
+// replay rebuilds the room after the first --at seconds of a trace, the robots must be where the recorded
+// run had them. With --check the robots are also moved by the simulation, one tick at a time, which must
+// put them there too.
+fn replay(path: &str) {
+    let (start, events) = trace::open::<Start, Ticked>(path, 14);
+    let mut room = Room::new(start.width, start.height, start.robots.clone());
+    let mut simulation = trace::check().then(|| room.clone());
+    let mut second = 0;
+    for event in events.take(trace::at()) {
+        room.apply(&start.robots, &event);
+        if room.fingerprint() != event.positions {
+            println!("Second {} differs, the robots were recorded somewhere else than replayed", event.second);
+            std::process::exit(1);
+        }
+        if let Some(simulation) = simulation.as_mut() {
+            simulation.tick();
+            if event.second != second + 1 || simulation.fingerprint() != event.positions {
+                println!("Second {} differs, the robots were recorded somewhere else than simulated", event.second);
+                std::process::exit(1);
+            }
+        }
+        second = event.second;
+    }
+    print!("{}", room);
+    println!("Replayed {} seconds", second);
 }
 
 fn main() {
+    if let Some(path) = trace::replay() {
+        replay(&path);
+        return;
+    }
     let filename = std::env::args().nth(1).expect("No filename provided");
     let width = std::env::args().nth(2).expect("No width provided").parse::<u32>().unwrap();
     let height = std::env::args().nth(3).expect("No height provided").parse::<u32>().unwrap();
-    let robots: Vec<Robot> = std::fs::read_to_string(filename)
-        .expect("Failed to read file")
-        .lines()
-        .map(|line| Robot::from(line))
+    let robots: Vec<Robot> = input::lines(filename)
+        .map(|line| Robot::from(line.as_str()))
         .collect();
     let mut room = Room::new(width, height, robots);
+    room.colour = ansi::enabled(std::env::args().any(|arg| arg == "--color"));
+    if std::env::args().any(|arg| arg == "--repl") {
+        repl(room);
+        return;
+    }
+    if std::env::args().any(|arg| arg == "--debug") {
+        Debugger::new(Simulation { room, second: 0 }).run();
+        return;
+    }
+    let initial = room.clone();
     let mut clone = room.clone();
     println!("{}", room);
     let mut density = 0;
+    let mut recorder = trace::recorder(14, &Start { width, height, robots: room.robots.clone() });
+    let mut progress = Progress::new("Ticking", (width * height) as u64).timeout(progress::timeout());
     for i in 0..width*height {
+        if !progress.step() {
+            println!(
+                "Timed out after {:.1}s, ticked {} of {} seconds",
+                progress.elapsed().as_secs_f64(),
+                i,
+                width * height
+            );
+            println!("Highest density so far: {}", density);
+            if let Some(recorder) = recorder.as_mut() {
+                recorder.flush();
+            }
+            std::process::exit(progress::TIMED_OUT);
+        }
         room.tick();
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.record(&Ticked { second: i + 1, positions: room.fingerprint() });
+        }
         let new_density = room.density();
         if new_density > density {
             density = new_density;
             println!("{}:\n{}", i,room);
         }
     }
+    progress.finish();
     println!("Safety factor: {}", room.safety_factor());
     let mut i = 0;
     while clone.density() != density {
@@ -153,4 +365,60 @@ fn main() {
     }
     println!("{}", clone);
     println!("Seconds: {}", i);
+
+    if let Some(path) = std::env::args().skip_while(|arg| arg != "--render").nth(1) {
+        clone.render(&path);
+        println!("Rendered to {}", path);
+    }
+    if let Some(path) = std::env::args().skip_while(|arg| arg != "--dump-state").nth(1) {
+        // robots are dumped as they are after --second seconds, 100 by default
+        let second = match std::env::args().skip_while(|arg| arg != "--second").nth(1) {
+            Some(second) => second.parse::<u32>().expect("Invalid --second"),
+            None => 100,
+        };
+        let mut room = initial;
+        for _ in 0..second {
+            room.tick();
+        }
+        room.dump_state(second, &path);
+        println!("State dumped to {}", path);
+    }
+}
+
+// repl lets you move the robots around in time and look at the room until quit or end of input
+fn repl(room: Room) {
+    let help = "Commands:
+  tick <n>       move the robots n seconds forward
+  show           print the room
+  robots         print robot positions and velocities
+  safety         safety factor of the current positions
+  density        number of tiles with at least one robot
+  reset          go back to second 0
+  quit";
+    let initial = room.clone();
+    println!("{} robots in a {}x{} room, type help for commands", room.robots.len(), room.width, room.height);
+    // the room and the seconds gone by since the start
+    repl::run(&mut (room, 0), help, |(_, second)| format!("[{}s] > ", second), |(room, second), words| {
+        match words {
+            ["tick", n] => match n.parse::<u32>() {
+                Ok(n) => {
+                    for _ in 0..n {
+                        room.tick();
+                    }
+                    *second += n;
+                }
+                Err(_) => println!("Invalid number of seconds"),
+            },
+            ["show"] => print!("{}", room),
+            ["robots"] => room.robots.iter().for_each(|robot| println!("p={},{} v={},{}", robot.x, robot.y, robot.v_x, robot.v_y)),
+            ["safety"] => println!("{}", room.safety_factor()),
+            ["density"] => println!("{}", room.density()),
+            ["reset"] => {
+                *room = initial.clone();
+                *second = 0;
+            }
+            _ => return false,
+        }
+        true
+    });
 }
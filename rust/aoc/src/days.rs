@@ -0,0 +1,181 @@
+use std::{
+    fs::read_to_string,
+    io::Read,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+// every day lives in <root>/NN/rust, the runner itself in <root>/rust/aoc
+pub fn root() -> PathBuf {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+    root.canonicalize().unwrap_or(root)
+}
+
+pub fn registry() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("days.txt")
+}
+
+pub fn dir(day: u32) -> PathBuf {
+    root().join(format!("{:02}", day))
+}
+
+pub fn default_input(day: u32) -> PathBuf {
+    dir(day).join("input.txt")
+}
+
+fn binary(day: u32) -> PathBuf {
+    dir(day).join(format!("rust/target/release/day-{}", day))
+}
+
+#[derive(Clone, Debug)]
+pub struct Part {
+    pub day: u32,
+    pub part: u32,
+    answer: String,
+    occurrence: usize,
+    args: Vec<String>,
+}
+
+pub struct Run {
+    pub answer: String,
+    pub wall_time: Duration,
+    // peak resident set size of the solver process, in kilobytes
+    pub peak_memory: u64,
+    pub output: String,
+}
+
+impl Part {
+    fn parse(line: &str) -> Result<Part, String> {
+        let columns: Vec<&str> = line.split('|').map(|column| column.trim()).collect();
+        if columns.len() != 5 {
+            return Err(format!("expected 5 columns, found {}", columns.len()));
+        }
+        let number = |column: &str| column.parse::<usize>().map_err(|_| format!("`{}` is not a number", column));
+        // occurrences count from 1, the first line holding the answer
+        let occurrence = number(columns[3])?;
+        if occurrence == 0 {
+            return Err("the occurrence of the answer line starts at 1".to_string());
+        }
+        Ok(Part {
+            day: number(columns[0])? as u32,
+            part: number(columns[1])? as u32,
+            answer: columns[2].to_string(),
+            occurrence,
+            args: columns[4].split_whitespace().map(|arg| arg.to_string()).collect(),
+        })
+    }
+
+    // run solves the part for the given input with the release build of the day,
+    // which has to be built beforehand
+    pub fn run(&self, input: &Path) -> Result<Run, String> {
+        self.run_within(input, None)
+    }
+
+    // run_within is run, but the solver is killed once it has run for longer than the limit
+    pub fn run_within(&self, input: &Path, limit: Option<Duration>) -> Result<Run, String> {
+        let start = Instant::now();
+        let mut child = Command::new(binary(self.day))
+            .arg(input)
+            .args(&self.args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("cannot start day {}: {}", self.day, e))?;
+        // stderr is drained on its own thread so a chatty solver can never block on a full pipe
+        let mut stderr = child.stderr.take().unwrap();
+        let errors = thread::spawn(move || {
+            let mut errors = String::new();
+            stderr.read_to_string(&mut errors).ok();
+            errors
+        });
+        // the watchdog kills the solver when the limit is over, it is stopped before the solver is reaped
+        // so the process it kills can't be another one reusing the id
+        let (stop, stopped) = mpsc::channel::<()>();
+        let pid = child.id();
+        let watchdog = thread::spawn(move || match limit {
+            Some(limit) if stopped.recv_timeout(limit) == Err(RecvTimeoutError::Timeout) => {
+                unsafe { libc::kill(pid as libc::pid_t, libc::SIGKILL) };
+                true
+            }
+            _ => false,
+        });
+        let mut output = String::new();
+        let read = child.stdout.take().unwrap().read_to_string(&mut output);
+        let _ = stop.send(());
+        let killed = watchdog.join().unwrap_or(false);
+        let (success, peak_memory) = wait(pid);
+        read.map_err(|e| e.to_string())?;
+        if killed {
+            return Err(format!("day {} part {} was stopped after {}s", self.day, self.part, limit.unwrap_or_default().as_secs_f64()));
+        }
+        let wall_time = start.elapsed();
+        let errors = errors.join().unwrap_or_default();
+        if !success {
+            let reason = errors.lines().find(|line| !line.trim().is_empty()).unwrap_or("no error output");
+            return Err(format!("day {} part {} failed: {}", self.day, self.part, reason));
+        }
+        let answer = self
+            .find_answer(&output)
+            .ok_or(format!("day {} part {}: no `{}` line in the output", self.day, self.part, self.answer))?;
+        Ok(Run { answer, wall_time, peak_memory, output })
+    }
+
+    // same_run tells if both parts are solved by the same run of the day, which prints every answer it finds
+    pub fn same_run(&self, other: &Part) -> bool {
+        self.day == other.day && self.args == other.args
+    }
+
+    // find_answer picks the first word after the answer prefix, on the expected occurrence of the line
+    pub fn find_answer(&self, output: &str) -> Option<String> {
+        output
+            .lines()
+            .filter(|line| line.starts_with(&self.answer))
+            .nth(self.occurrence - 1)
+            .and_then(|line| line[self.answer.len()..].split_whitespace().next())
+            .map(|answer| answer.to_string())
+    }
+}
+
+// wait reaps the process and returns whether it exited successfully and its peak memory usage,
+// std's Child does not expose the resource usage of the process so this goes through wait4
+fn wait(pid: u32) -> (bool, u64) {
+    let mut status = 0;
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::wait4(pid as libc::pid_t, &mut status, 0, &mut usage) };
+    if result == -1 {
+        return (false, 0);
+    }
+    let success = libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0;
+    // ru_maxrss is reported in kilobytes on linux
+    (success, usage.ru_maxrss as u64)
+}
+
+pub fn load() -> Vec<Part> {
+    let registry = registry();
+    read_to_string(&registry)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", registry.display(), e))
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(idx, line)| {
+            Part::parse(line).unwrap_or_else(|e| panic!("{}:{}: {}", registry.display(), idx + 1, e))
+        })
+        .collect()
+}
+
+// build compiles the release binary of the day, the solvers are far too slow in debug builds
+pub fn build(day: u32) -> Result<(), String> {
+    let status = Command::new("cargo")
+        .args(["build", "--release", "--quiet", "--manifest-path"])
+        .arg(dir(day).join("rust/Cargo.toml"))
+        .status()
+        .map_err(|e| format!("cannot run cargo: {}", e))?;
+    if !status.success() {
+        return Err(format!("day {} does not build", day));
+    }
+    Ok(())
+}
@@ -0,0 +1,286 @@
+use std::{any::type_name, fs::read_to_string, path::Path, str::FromStr};
+
+// a problem found in an input file, line numbers start at 1 and 0 means the file as a whole
+pub struct Violation {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Violation {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self { line, message: message.into() }
+    }
+}
+
+// lint checks the input file against the structure the solver of the day expects,
+// without running the solver, and prints every violation found
+pub fn lint(day: u32, filename: &Path) -> bool {
+    let input = match read_to_string(filename) {
+        Ok(input) => input,
+        Err(e) => {
+            println!("{}: cannot read file: {}", filename.display(), e);
+            return false;
+        }
+    };
+    let violations = match check(day, &input) {
+        Some(violations) => violations,
+        None => {
+            println!("There are no checks for day {}", day);
+            return false;
+        }
+    };
+    for violation in violations.iter() {
+        if violation.line == 0 {
+            println!("{}: {}", filename.display(), violation.message);
+        } else {
+            println!("{}:{}: {}", filename.display(), violation.line, violation.message);
+        }
+    }
+    if violations.is_empty() {
+        println!("{}: no problems found", filename.display());
+    } else {
+        println!("{}: {} problem(s) found", filename.display(), violations.len());
+    }
+    violations.is_empty()
+}
+
+pub fn check(day: u32, input: &str) -> Option<Vec<Violation>> {
+    let mut violations = Vec::new();
+    if input.trim().is_empty() {
+        violations.push(Violation::new(0, "file is empty"));
+        return Some(violations);
+    }
+    match day {
+        1 => {
+            check_lines(input, &mut violations, |line| integers::<i32>(line, 2, usize::MAX));
+            check_columns(input, &mut violations);
+        }
+        2 => check_lines(input, &mut violations, |line| integers::<i32>(line, 1, usize::MAX)),
+        3 => {
+            if !input.contains("mul(") {
+                violations.push(Violation::new(0, "no mul instructions in the file"));
+            }
+        }
+        4 => check_grid(input, &mut violations, |c| "XMAS".contains(c)),
+        5 => check_manual(input, &mut violations),
+        6 => {
+            check_grid(input, &mut violations, |c| ".#^v<>".contains(c));
+            check_guard(input, &mut violations);
+        }
+        7 => check_lines(input, &mut violations, calibration),
+        8 => check_grid(input, &mut violations, |c| c == '.' || c.is_ascii_alphanumeric()),
+        9 => check_single_line(input, &mut violations, |line| {
+            match line.chars().position(|c| !c.is_ascii_digit()) {
+                Some(idx) => Err(format!("`{}` at column {} is not a digit", line.chars().nth(idx).unwrap(), idx + 1)),
+                None => Ok(()),
+            }
+        }),
+        10 => check_grid(input, &mut violations, |c| c.is_ascii_digit()),
+        11 => check_single_line(input, &mut violations, |line| integers::<usize>(line, 1, usize::MAX)),
+        12 => check_grid(input, &mut violations, |c| c.is_ascii_uppercase()),
+        13 => check_machines(input, &mut violations),
+        14 => check_lines(input, &mut violations, robot),
+        _ => return None,
+    }
+    // checks over the whole file run after the per-line ones, the report goes in file order
+    // with the file-wide violations first
+    violations.sort_by_key(|violation| violation.line);
+    Some(violations)
+}
+
+// check_lines runs the check on every line, blank lines are never expected
+fn check_lines(input: &str, violations: &mut Vec<Violation>, check: impl Fn(&str) -> Result<(), String>) {
+    for (idx, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            violations.push(Violation::new(idx + 1, "blank line"));
+        } else if let Err(message) = check(line) {
+            violations.push(Violation::new(idx + 1, message));
+        }
+    }
+}
+
+fn check_single_line(input: &str, violations: &mut Vec<Violation>, check: impl Fn(&str) -> Result<(), String>) {
+    let lines: Vec<&str> = input.trim_end().lines().collect();
+    if lines.len() > 1 {
+        violations.push(Violation::new(2, format!("expected a single line, found {}", lines.len())));
+    }
+    if let Err(message) = check(lines[0]) {
+        violations.push(Violation::new(1, message));
+    }
+}
+
+// check_grid verifies the input is a rectangle of allowed characters
+fn check_grid(input: &str, violations: &mut Vec<Violation>, allowed: impl Fn(char) -> bool) {
+    let lines: Vec<&str> = input.trim_end_matches('\n').lines().collect();
+    let width = lines[0].chars().count();
+    for (idx, line) in lines.iter().enumerate() {
+        let length = line.chars().count();
+        if length != width {
+            violations.push(Violation::new(idx + 1, format!("row has {} cells, the first row has {}", length, width)));
+        }
+        if let Some((col, c)) = line.chars().enumerate().find(|(_, c)| !allowed(*c)) {
+            violations.push(Violation::new(idx + 1, format!("unexpected `{}` at column {}", c, col + 1)));
+        }
+    }
+}
+
+// check_columns verifies every line has as many values as the first one, one per list,
+// lines with less than two values are already reported by the integers check
+fn check_columns(input: &str, violations: &mut Vec<Violation>) {
+    let mut lines = input.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+    let Some((_, first)) = lines.next() else { return };
+    let columns = first.split_whitespace().count();
+    for (idx, line) in lines {
+        let count = line.split_whitespace().count();
+        if count >= 2 && count != columns {
+            violations.push(Violation::new(idx + 1, format!("expected {} lists like the first line, found {}", columns, count)));
+        }
+    }
+}
+
+fn check_guard(input: &str, violations: &mut Vec<Violation>) {
+    let guards: Vec<usize> = input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.contains(['^', 'v', '<', '>']))
+        .map(|(idx, _)| idx + 1)
+        .collect();
+    let count: usize = input.chars().filter(|c| "^v<>".contains(*c)).count();
+    if count == 0 {
+        violations.push(Violation::new(0, "there is no guard in the map"));
+    } else if count > 1 {
+        for line in guards {
+            violations.push(Violation::new(line, format!("one of {} guards found in the map, expected exactly one", count)));
+        }
+    }
+}
+
+// check_manual verifies the page ordering rules come first, then a blank line, then the updates
+fn check_manual(input: &str, violations: &mut Vec<Violation>) {
+    let mut in_updates = false;
+    let mut updates = 0;
+    for (idx, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            if in_updates {
+                violations.push(Violation::new(idx + 1, "blank line between updates"));
+            }
+            in_updates = true;
+            continue;
+        }
+        if line.contains('|') {
+            if in_updates {
+                violations.push(Violation::new(idx + 1, "rule found after the updates started"));
+            }
+            let parts: Vec<&str> = line.split('|').collect();
+            if parts.len() != 2 || parts.iter().any(|part| part.parse::<u32>().is_err()) {
+                violations.push(Violation::new(idx + 1, "rules look like `47|53`"));
+            }
+        } else {
+            in_updates = true;
+            updates += 1;
+            let pages: Vec<&str> = line.split(',').collect();
+            if let Some(page) = pages.iter().find(|page| page.parse::<u32>().is_err()) {
+                violations.push(Violation::new(idx + 1, format!("`{}` is not a page number", page)));
+            } else if pages.len().is_multiple_of(2) {
+                violations.push(Violation::new(idx + 1, format!("update has {} pages, it needs an odd number to have a middle page", pages.len())));
+            }
+        }
+    }
+    if updates == 0 {
+        violations.push(Violation::new(0, "there are no updates in the file"));
+    }
+}
+
+// check_machines verifies the file is made of triples of button a, button b and prize lines
+fn check_machines(input: &str, violations: &mut Vec<Violation>) {
+    let mut expected = 0;
+    let mut last_line = 0;
+    for (idx, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            if expected != 0 {
+                violations.push(Violation::new(idx + 1, "blank line in the middle of a claw machine"));
+            }
+            continue;
+        }
+        last_line = idx + 1;
+        let result = match expected {
+            0 => button(line, "Button A: "),
+            1 => button(line, "Button B: "),
+            _ => prize(line),
+        };
+        if let Err(message) = result {
+            violations.push(Violation::new(idx + 1, message));
+        }
+        expected = (expected + 1) % 3;
+    }
+    if expected != 0 {
+        violations.push(Violation::new(last_line, "the last claw machine is incomplete"));
+    }
+}
+
+// integers checks the line holds min to max integers of the type the solver parses them into
+fn integers<T: FromStr>(line: &str, min: usize, max: usize) -> Result<(), String> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    if let Some(token) = tokens.iter().find(|token| token.parse::<T>().is_err()) {
+        if token.parse::<i128>().is_ok() {
+            return Err(format!("`{}` is out of range, the values are read as {}", token, type_name::<T>()));
+        }
+        return Err(format!("`{}` is not an integer", token));
+    }
+    if tokens.len() < min || tokens.len() > max {
+        if min == max {
+            return Err(format!("expected {} integers, found {}", min, tokens.len()));
+        }
+        return Err(format!("expected at least {} integers, found {}", min, tokens.len()));
+    }
+    Ok(())
+}
+
+fn calibration(line: &str) -> Result<(), String> {
+    let (result, operands) = line.split_once(": ").ok_or("calibrations look like `190: 10 19`")?;
+    if result.parse::<i64>().is_err() {
+        return Err(format!("`{}` is not an integer", result));
+    }
+    if let Some(operand) = operands.split(' ').find(|operand| operand.parse::<i64>().is_err()) {
+        return Err(format!("`{}` is not an integer, operands are separated by single spaces", operand));
+    }
+    Ok(())
+}
+
+fn button(line: &str, prefix: &str) -> Result<(), String> {
+    let error = format!("expected `{}X+94, Y+34`", prefix);
+    let coords = line.strip_prefix(prefix).ok_or(error.clone())?;
+    let (x, y) = coords.split_once(", ").ok_or(error.clone())?;
+    let x = x.strip_prefix("X+").ok_or(error.clone())?;
+    let y = y.strip_prefix("Y+").ok_or(error.clone())?;
+    if x.parse::<u64>().is_err() || y.parse::<u64>().is_err() {
+        return Err(error);
+    }
+    Ok(())
+}
+
+fn prize(line: &str) -> Result<(), String> {
+    let error = "expected `Prize: X=8400, Y=5400`".to_string();
+    let coords = line.strip_prefix("Prize: ").ok_or(error.clone())?;
+    let (x, y) = coords.split_once(", ").ok_or(error.clone())?;
+    let x = x.strip_prefix("X=").ok_or(error.clone())?;
+    let y = y.strip_prefix("Y=").ok_or(error.clone())?;
+    if x.parse::<u64>().is_err() || y.parse::<u64>().is_err() {
+        return Err(error);
+    }
+    Ok(())
+}
+
+fn robot(line: &str) -> Result<(), String> {
+    let error = "expected `p=0,4 v=3,-3`".to_string();
+    let (position, velocity) = line.split_once(' ').ok_or(error.clone())?;
+    let (x, y) = position.strip_prefix("p=").and_then(|p| p.split_once(',')).ok_or(error.clone())?;
+    let (v_x, v_y) = velocity.strip_prefix("v=").and_then(|v| v.split_once(',')).ok_or(error.clone())?;
+    if x.parse::<u32>().is_err() || y.parse::<u32>().is_err() {
+        return Err("robot positions are non-negative integers".to_string());
+    }
+    if v_x.parse::<i32>().is_err() || v_y.parse::<i32>().is_err() {
+        return Err("robot velocities are integers".to_string());
+    }
+    Ok(())
+}
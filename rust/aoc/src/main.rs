@@ -0,0 +1,110 @@
+use std::{fs, path::PathBuf, time::Duration};
+
+use render::ansi;
+
+mod days;
+mod lint;
+mod new;
+mod run;
+mod serve;
+mod snapshot;
+
+const USAGE: &str = "Usage:
+  aoc run --all          solve every registered part, in parallel
+  aoc run <day>...       solve the parts of the given days, in parallel
+  aoc lint <day> [file]  check the input file of the day without solving it
+  aoc new <day>          create NN/rust for a new day and register it with the runner
+  aoc snapshot [day...]  compare the output of days with renderings to their snapshots
+  aoc snapshot --accept  keep the changed outputs found by the last comparison
+  aoc serve [--port N] [--solve-timeout S]
+                         answer POST /days/{day}/parts/{part} on localhost, port 2024 by default,
+                         solvers running longer than S seconds (60 by default) are killed
+  aoc diff <left> <right> [--color]
+                         compare two text grids cell by cell, with the counts of every kind of change";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(|arg| arg.as_str()) {
+        Some("run") => {
+            let parts = days::load();
+            let parts = if args.iter().any(|arg| arg == "--all") {
+                parts
+            } else {
+                let selected: Vec<u32> = args[1..].iter().map(|day| parse_day(day)).collect();
+                parts.into_iter().filter(|part| selected.contains(&part.day)).collect()
+            };
+            if parts.is_empty() {
+                exit_with_usage();
+            }
+            run::run(parts);
+        }
+        Some("lint") => {
+            let day = parse_day(args.get(1).unwrap_or_else(|| exit_with_usage()));
+            let filename = args.get(2).map(PathBuf::from).unwrap_or_else(|| days::default_input(day));
+            if !lint::lint(day, &filename) {
+                std::process::exit(1);
+            }
+        }
+        Some("new") => {
+            let day = parse_day(args.get(1).unwrap_or_else(|| exit_with_usage()));
+            if let Err(e) = new::new(day) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        Some("snapshot") => {
+            let mut selected: Vec<u32> = args[1..].iter().filter(|arg| *arg != "--accept").map(|day| parse_day(day)).collect();
+            if selected.is_empty() {
+                selected = snapshot::DAYS.to_vec();
+            }
+            if args.iter().any(|arg| arg == "--accept") {
+                snapshot::accept(&selected);
+            } else if !snapshot::check(&selected) {
+                std::process::exit(1);
+            }
+        }
+        Some("serve") => {
+            let port = match args.iter().skip_while(|arg| *arg != "--port").nth(1) {
+                Some(port) => port.parse().unwrap_or_else(|_| exit_with_usage()),
+                None => 2024,
+            };
+            let limit = match args.iter().skip_while(|arg| *arg != "--solve-timeout").nth(1) {
+                Some(seconds) => match seconds.parse::<f64>() {
+                    Ok(seconds) if seconds > 0.0 && seconds.is_finite() => Duration::from_secs_f64(seconds),
+                    _ => exit_with_usage(),
+                },
+                None => serve::SOLVE_LIMIT,
+            };
+            serve::serve(port, limit);
+        }
+        Some("diff") => {
+            let files: Vec<&String> = args[1..].iter().filter(|arg| *arg != "--color").collect();
+            let [left, right] = files.as_slice() else { exit_with_usage() };
+            let read = |path: &String| {
+                fs::read_to_string(path).unwrap_or_else(|e| {
+                    eprintln!("cannot read {}: {}", path, e);
+                    std::process::exit(2);
+                })
+            };
+            let diff = griddiff::diff(&read(left), &read(right));
+            print!("{}", diff.report(ansi::enabled(args.iter().any(|arg| arg == "--color"))));
+            // like diff(1), differences are reported with exit code 1
+            if !diff.is_same() {
+                std::process::exit(1);
+            }
+        }
+        _ => exit_with_usage(),
+    }
+}
+
+fn parse_day(day: &str) -> u32 {
+    day.parse().unwrap_or_else(|_| {
+        eprintln!("`{}` is not a day", day);
+        exit_with_usage()
+    })
+}
+
+fn exit_with_usage() -> ! {
+    eprintln!("{}", USAGE);
+    std::process::exit(2);
+}
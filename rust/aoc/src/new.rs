@@ -0,0 +1,40 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+
+use crate::days;
+
+const CARGO_TOML: &str = include_str!("../template/Cargo.toml.tpl");
+const MAIN_RS: &str = include_str!("../template/main.rs.tpl");
+
+// new creates NN/rust for the day from the template, with an empty input file,
+// and registers both parts of the day with the runner
+pub fn new(day: u32) -> Result<(), String> {
+    let dir = days::dir(day);
+    if dir.exists() {
+        return Err(format!("{} already exists", dir.display()));
+    }
+    if days::load().iter().any(|part| part.day == day) {
+        return Err(format!("day {} is already registered in {}", day, days::registry().display()));
+    }
+    let src = dir.join("rust/src");
+    fs::create_dir_all(&src).map_err(|e| format!("cannot create {}: {}", src.display(), e))?;
+    let files = [
+        (dir.join("input.txt"), String::new()),
+        (dir.join("rust/Cargo.toml"), CARGO_TOML.replace("{day}", &day.to_string())),
+        (src.join("main.rs"), MAIN_RS.to_string()),
+    ];
+    for (path, contents) in files.iter() {
+        fs::write(path, contents).map_err(|e| format!("cannot write {}: {}", path.display(), e))?;
+        println!("Created {}", path.display());
+    }
+
+    let registry = days::registry();
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(&registry)
+        .map_err(|e| format!("cannot open {}: {}", registry.display(), e))?;
+    writeln!(file, "{} | 1 | Part 1: | 1 |\n{} | 2 | Part 2: | 1 |", day, day)
+        .map_err(|e| format!("cannot write {}: {}", registry.display(), e))?;
+    println!("Registered day {} in {}", day, registry.display());
+    Ok(())
+}
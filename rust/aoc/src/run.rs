@@ -0,0 +1,111 @@
+use std::{
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+use crate::days::{self, Part, Run};
+
+// run solves the given parts on a pool of worker threads, one solver process per day (or per set of
+// arguments, for days run once for each part), and prints a report with the most expensive runs first
+pub fn run(parts: Vec<Part>) {
+    let mut built: Vec<u32> = parts.iter().map(|part| part.day).collect();
+    built.dedup();
+    for day in built {
+        if let Err(e) = days::build(day) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+
+    // the parts solved by the same run are grouped, a day printing both answers is only solved once
+    let mut groups: Vec<Vec<Part>> = Vec::new();
+    for part in parts {
+        match groups.iter_mut().find(|group| group[0].same_run(&part)) {
+            Some(group) => group.push(part),
+            None => groups.push(vec![part]),
+        }
+    }
+
+    let workers = thread::available_parallelism().map(|n| n.get()).unwrap_or(1).min(groups.len());
+    let queue = Arc::new(Mutex::new(groups));
+    let (sender, receiver) = mpsc::channel();
+    let mut handles = Vec::new();
+    for _ in 0..workers {
+        let queue = Arc::clone(&queue);
+        let sender = sender.clone();
+        handles.push(thread::spawn(move || loop {
+            let next = queue.lock().unwrap().pop();
+            let Some(group) = next else { break };
+            let run = solve(&group);
+            sender.send((group, run)).unwrap();
+        }));
+    }
+    drop(sender);
+    let mut results: Vec<(Vec<Part>, Result<Solved, String>)> = receiver.iter().collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    // failed runs go at the top, they are the first thing to look at
+    results.sort_by(|(_, a), (_, b)| match (a, b) {
+        (Ok(a), Ok(b)) => b.run.wall_time.cmp(&a.run.wall_time),
+        (Err(_), Ok(_)) => std::cmp::Ordering::Less,
+        (Ok(_), Err(_)) => std::cmp::Ordering::Greater,
+        (Err(_), Err(_)) => std::cmp::Ordering::Equal,
+    });
+    print_report(&results);
+}
+
+// Solved is one run of a day with the answers of every part it solved, in the order of the group
+struct Solved {
+    run: Run,
+    answers: Vec<String>,
+}
+
+fn solve(group: &[Part]) -> Result<Solved, String> {
+    let run = group[0].run(&days::default_input(group[0].day))?;
+    let mut answers = vec![run.answer.clone()];
+    for part in &group[1..] {
+        let answer = part
+            .find_answer(&run.output)
+            .ok_or(format!("day {} part {}: no answer in the output", part.day, part.part))?;
+        answers.push(answer);
+    }
+    Ok(Solved { run, answers })
+}
+
+// print_report prints a line per part, the wall time and peak memory are those of the run
+// and are only printed on the first part it solved
+fn print_report(results: &[(Vec<Part>, Result<Solved, String>)]) {
+    println!("{:>3} {:>4}  {:<20} {:>12} {:>12}", "Day", "Part", "Answer", "Wall time", "Peak memory");
+    for (group, result) in results.iter() {
+        match result {
+            Ok(solved) => {
+                for (idx, (part, answer)) in group.iter().zip(solved.answers.iter()).enumerate() {
+                    if idx == 0 {
+                        println!(
+                            "{:>3} {:>4}  {:<20} {:>10.3}ms {:>9.1}MiB",
+                            part.day,
+                            part.part,
+                            answer,
+                            solved.run.wall_time.as_secs_f64() * 1000.0,
+                            solved.run.peak_memory as f64 / 1024.0
+                        );
+                    } else {
+                        println!("{:>3} {:>4}  {:<20} {:>12}", part.day, part.part, answer, "same run");
+                    }
+                }
+            }
+            Err(e) => {
+                let parts: Vec<String> = group.iter().map(|part| part.part.to_string()).collect();
+                println!("{:>3} {:>4}  FAILED: {}", group[0].day, parts.join(","), e);
+            }
+        }
+    }
+    let total: f64 = results
+        .iter()
+        .filter_map(|(_, result)| result.as_ref().ok())
+        .map(|solved| solved.run.wall_time.as_secs_f64() * 1000.0)
+        .sum();
+    println!("Total solver time: {:.3}ms", total);
+}
@@ -0,0 +1,196 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::days::{self, Part};
+
+struct Response {
+    status: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn error(status: &'static str, message: &str) -> Self {
+        Self { status, body: format!("{{\"error\":{}}}", json_string(message)) }
+    }
+}
+
+// MAX_BODY is the largest puzzle input accepted, bigger bodies are answered with 413 without being read
+const MAX_BODY: usize = 64 * 1024 * 1024;
+// TIMEOUT bounds every read and write on a connection, so a silent client can't hold a worker forever
+const TIMEOUT: Duration = Duration::from_secs(10);
+// QUEUED is how many accepted connections wait for a worker, per worker, before accepting slows down
+const QUEUED: usize = 4;
+// SOLVE_LIMIT is how long a solver may run before it is killed, unless --solve-timeout says otherwise
+pub const SOLVE_LIMIT: Duration = Duration::from_secs(60);
+
+struct Server {
+    parts: Vec<Part>,
+    // days are built the first time they are asked for, each behind its own lock so a build
+    // only holds up the requests for the same day
+    built: Mutex<HashMap<u32, Arc<Mutex<bool>>>>,
+    requests: AtomicUsize,
+    limit: Duration,
+}
+
+// serve answers `POST /days/{day}/parts/{part}` requests, whose body is the puzzle input,
+// with the answer and timing of the solver. It only listens on localhost.
+pub fn serve(port: u16, limit: Duration) {
+    let listener = TcpListener::bind(("127.0.0.1", port)).expect("Failed to bind the port");
+    println!("Listening on {}", listener.local_addr().unwrap());
+    let server = Arc::new(Server {
+        parts: days::load(),
+        built: Mutex::new(HashMap::new()),
+        requests: AtomicUsize::new(0),
+        limit,
+    });
+    // connections are handled by a fixed pool of workers, one connection at a time each
+    let workers = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let (sender, receiver) = mpsc::sync_channel::<TcpStream>(workers * QUEUED);
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..workers {
+        let server = Arc::clone(&server);
+        let receiver = Arc::clone(&receiver);
+        thread::spawn(move || loop {
+            let next = receiver.lock().unwrap().recv();
+            let Ok(stream) = next else { break };
+            server.handle(stream);
+        });
+    }
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        if sender.send(stream).is_err() {
+            break;
+        }
+    }
+}
+
+impl Server {
+    fn handle(&self, mut stream: TcpStream) {
+        if stream.set_read_timeout(Some(TIMEOUT)).is_err() || stream.set_write_timeout(Some(TIMEOUT)).is_err() {
+            return;
+        }
+        let response = match read_request(&mut stream) {
+            Ok((method, path, body)) => self.route(&method, &path, body),
+            Err(response) => response,
+        };
+        let _ = write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            response.status,
+            response.body.len(),
+            response.body
+        );
+    }
+
+    fn route(&self, method: &str, path: &str, body: Vec<u8>) -> Response {
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        let (day, part) = match segments.as_slice() {
+            ["days", day, "parts", part] => match (day.parse::<u32>(), part.parse::<u32>()) {
+                (Ok(day), Ok(part)) => (day, part),
+                _ => return Response::error("404 Not Found", "days and parts are numbers"),
+            },
+            _ => return Response::error("404 Not Found", "the only route is POST /days/{day}/parts/{part}"),
+        };
+        if method != "POST" {
+            return Response::error("405 Method Not Allowed", "send the puzzle input with POST");
+        }
+        let Some(part) = self.parts.iter().find(|p| p.day == day && p.part == part) else {
+            return Response::error("404 Not Found", &format!("day {} part {} is not registered", day, part));
+        };
+        if let Err(message) = self.build(day) {
+            return Response::error("500 Internal Server Error", &message);
+        }
+        // solvers read their input from a file
+        let id = self.requests.fetch_add(1, Ordering::SeqCst);
+        let input = std::env::temp_dir().join(format!("aoc-serve-{}-{}.txt", std::process::id(), id));
+        if let Err(e) = fs::write(&input, body) {
+            return Response::error("500 Internal Server Error", &format!("cannot store the input: {}", e));
+        }
+        let run = part.run_within(&input, Some(self.limit));
+        let _ = fs::remove_file(&input);
+        match run {
+            Ok(run) => Response {
+                status: "200 OK",
+                body: format!(
+                    "{{\"day\":{},\"part\":{},\"answer\":{},\"wall_time_ms\":{:.3},\"peak_memory_kb\":{}}}",
+                    day,
+                    part.part,
+                    json_string(&run.answer),
+                    run.wall_time.as_secs_f64() * 1000.0,
+                    run.peak_memory
+                ),
+            },
+            Err(message) => Response::error("422 Unprocessable Entity", &message),
+        }
+    }
+
+    fn build(&self, day: u32) -> Result<(), String> {
+        let lock = Arc::clone(self.built.lock().unwrap().entry(day).or_default());
+        let mut built = lock.lock().unwrap();
+        if !*built {
+            days::build(day)?;
+            *built = true;
+        }
+        Ok(())
+    }
+}
+
+// read_request returns the method, path and body of a HTTP/1.1 request, or the error response to send back
+fn read_request(stream: &mut TcpStream) -> Result<(String, String, Vec<u8>), Response> {
+    let bad_request = |message: String| Response::error("400 Bad Request", &message);
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).map_err(|e| bad_request(e.to_string()))?;
+    let mut words = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (words.next(), words.next()) else {
+        return Err(bad_request("malformed request line".to_string()));
+    };
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).map_err(|e| bad_request(e.to_string()))?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().map_err(|_| bad_request("invalid Content-Length".to_string()))?;
+            }
+        }
+    }
+    if content_length > MAX_BODY {
+        return Err(Response::error(
+            "413 Payload Too Large",
+            &format!("the input is {} bytes, at most {} are accepted", content_length, MAX_BODY),
+        ));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).map_err(|e| bad_request(e.to_string()))?;
+    Ok((method.to_string(), path.to_string(), body))
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
@@ -0,0 +1,97 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::days::{self, Part};
+
+// days whose text renderings (maps, disk layouts, gardens, rooms) are locked down by snapshots
+pub const DAYS: [u32; 5] = [6, 8, 9, 12, 14];
+
+fn dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("snapshots")
+}
+
+fn snapshot_path(day: u32) -> PathBuf {
+    dir().join(format!("day-{:02}.txt", day))
+}
+
+fn pending_path(day: u32) -> PathBuf {
+    dir().join(format!("day-{:02}.txt.new", day))
+}
+
+// check runs the days on their checked in input and compares the whole output with the snapshot,
+// changed outputs are written next to the snapshot as .new files to be reviewed and accepted
+pub fn check(selected: &[u32]) -> bool {
+    let parts = days::load();
+    let mut success = true;
+    for day in selected.iter() {
+        let Some(part) = parts.iter().find(|part| part.day == *day) else {
+            println!("day {}: not registered", day);
+            success = false;
+            continue;
+        };
+        match check_day(part) {
+            Ok(message) => println!("day {}: {}", day, message),
+            Err(message) => {
+                println!("day {}: {}", day, message);
+                success = false;
+            }
+        }
+    }
+    if !success {
+        println!("Review the .new files in {} and run `aoc snapshot --accept` to keep them", dir().display());
+    }
+    success
+}
+
+fn check_day(part: &Part) -> Result<String, String> {
+    days::build(part.day)?;
+    fs::create_dir_all(dir()).map_err(|e| e.to_string())?;
+    let output = part.run(&days::default_input(part.day))?.output;
+    let snapshot = snapshot_path(part.day);
+    let pending = pending_path(part.day);
+    let expected = match fs::read_to_string(&snapshot) {
+        Ok(expected) => expected,
+        Err(_) => {
+            fs::write(&pending, &output).map_err(|e| e.to_string())?;
+            return Err(format!("no snapshot yet, output written to {}", pending.display()));
+        }
+    };
+    if expected == output {
+        // a stale pending snapshot would otherwise be accepted by mistake later on
+        let _ = fs::remove_file(&pending);
+        return Ok("matches the snapshot".to_string());
+    }
+    fs::write(&pending, &output).map_err(|e| e.to_string())?;
+    print_changes(&expected, &output);
+    Err(format!("output changed, written to {}", pending.display()))
+}
+
+fn print_changes(expected: &str, actual: &str) {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    for idx in 0..expected.len().max(actual.len()) {
+        let (old, new) = (expected.get(idx), actual.get(idx));
+        if old != new {
+            println!("  line {}:", idx + 1);
+            if let Some(old) = old {
+                println!("  - {}", old);
+            }
+            if let Some(new) = new {
+                println!("  + {}", new);
+            }
+        }
+    }
+}
+
+// accept replaces the snapshots with the pending .new files
+pub fn accept(selected: &[u32]) {
+    for day in selected.iter() {
+        let pending = pending_path(*day);
+        if pending.exists() {
+            fs::rename(&pending, snapshot_path(*day)).expect("Failed to accept the snapshot");
+            println!("day {}: snapshot accepted", day);
+        }
+    }
+}
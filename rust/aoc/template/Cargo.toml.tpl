@@ -0,0 +1,6 @@
+[package]
+name = "day-{day}"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
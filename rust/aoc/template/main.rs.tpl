@@ -0,0 +1,40 @@
+use std::fs::read_to_string;
+
+fn main() {
+    let input = std::env::args().nth(1).expect("Please provide an input");
+    let lines = parse_input(&input);
+
+    println!("Part 1: {}", part_1(&lines));
+    println!("Part 2: {}", part_2(&lines));
+}
+
+fn parse_input(filename: &str) -> Vec<String> {
+    read_to_string(filename)
+        .expect("Failed to read file")
+        .lines()
+        .map(|line| line.to_string())
+        .collect()
+}
+
+fn part_1(lines: &[String]) -> u64 {
+    lines.len() as u64
+}
+
+fn part_2(lines: &[String]) -> u64 {
+    lines.len() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // paste the example from the puzzle description here
+    const EXAMPLE: &str = "";
+
+    #[test]
+    fn example() {
+        let lines: Vec<String> = EXAMPLE.lines().map(|line| line.to_string()).collect();
+        assert_eq!(part_1(&lines), 0);
+        assert_eq!(part_2(&lines), 0);
+    }
+}
@@ -0,0 +1,22 @@
+use std::{fs, process::Command};
+
+const LEFT: &str = "....#\n.XX..\n#....\n";
+const RIGHT: &str = "....#\n.X.#.\n#....\n";
+
+#[test]
+fn cli_reports_the_differences() {
+    let dir = std::env::temp_dir();
+    let left = dir.join(format!("aoc-diff-{}-left.txt", std::process::id()));
+    let right = dir.join(format!("aoc-diff-{}-right.txt", std::process::id()));
+    fs::write(&left, LEFT).unwrap();
+    fs::write(&right, RIGHT).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_aoc")).arg("diff").arg(&left).arg(&right).output().unwrap();
+    let same = Command::new(env!("CARGO_BIN_EXE_aoc")).arg("diff").arg(&left).arg(&left).output().unwrap();
+    let _ = fs::remove_file(&left);
+    let _ = fs::remove_file(&right);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(stdout.contains("2 cells differ"), "{}", stdout);
+    assert!(stdout.contains("(1, 3): '.' -> '#'"), "{}", stdout);
+    assert!(same.status.success());
+}
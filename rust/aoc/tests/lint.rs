@@ -0,0 +1,77 @@
+use std::{
+    fs,
+    path::Path,
+    process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+// every linted file gets its own name, the tests run in parallel
+static FILES: AtomicUsize = AtomicUsize::new(0);
+
+// lint runs `aoc lint` on the given contents and returns whether it passed and what it printed
+fn lint(day: u32, contents: &str) -> (bool, String) {
+    let file = FILES.fetch_add(1, Ordering::Relaxed);
+    let path = std::env::temp_dir().join(format!("aoc-lint-{}-{}-{}.txt", std::process::id(), day, file));
+    fs::write(&path, contents).unwrap();
+    let (success, stdout) = lint_file(day, &path);
+    let _ = fs::remove_file(&path);
+    (success, stdout)
+}
+
+fn lint_file(day: u32, path: &Path) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_aoc")).arg("lint").arg(day.to_string()).arg(path).output().unwrap();
+    (output.status.success(), String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// rejects asserts the file fails the check of the day with the message on the given line
+#[track_caller]
+fn rejects(day: u32, contents: &str, line: usize, message: &str) {
+    let (success, stdout) = lint(day, contents);
+    assert!(!success, "{}", stdout);
+    let expected = if line == 0 { format!(": {}", message) } else { format!(":{}: {}", line, message) };
+    assert!(stdout.lines().any(|printed| printed.ends_with(&expected)), "expected `{}` in\n{}", expected, stdout);
+}
+
+#[test]
+fn accepts_the_checked_in_inputs() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+    for day in 1..=14 {
+        let (success, stdout) = lint_file(day, &root.join(format!("{:02}/input.txt", day)));
+        assert!(success, "day {}: {}", day, stdout);
+    }
+}
+
+#[test]
+fn checks_the_integers_with_the_type_the_solver_reads() {
+    rejects(1, "3 9999999999\n4 3\n", 1, "`9999999999` is out of range, the values are read as i32");
+    rejects(1, "3 4\n4 x\n", 2, "`x` is not an integer");
+    rejects(1, "3 4\n4\n", 2, "expected at least 2 integers, found 1");
+    rejects(1, "3 4 5\n4 3\n1 2 3\n", 2, "expected 3 lists like the first line, found 2");
+    rejects(1, "3 4\n\n4 3\n", 2, "blank line");
+    rejects(2, "7 6 4 2 1\n1 2 -3000000000\n", 2, "`-3000000000` is out of range, the values are read as i32");
+    rejects(11, "-5 3\n", 1, "`-5` is out of range, the values are read as usize");
+    rejects(11, "125 17\n1 2\n", 2, "expected a single line, found 2");
+    rejects(7, "190: 10 19\n3267 81 40 27\n", 2, "calibrations look like `190: 10 19`");
+    rejects(7, "190: 10  19\n", 1, "`` is not an integer, operands are separated by single spaces");
+}
+
+#[test]
+fn checks_the_structure_of_each_day() {
+    rejects(3, "xmul[2,4]\n", 0, "no mul instructions in the file");
+    rejects(4, "XMAS\nXMA\n", 2, "row has 3 cells, the first row has 4");
+    rejects(4, "XMAS\nXMAZ\n", 2, "unexpected `Z` at column 4");
+    rejects(5, "47|53\n\n75,47\n", 3, "update has 2 pages, it needs an odd number to have a middle page");
+    rejects(5, "47|53\n\n75,47,61\n97|13\n", 4, "rule found after the updates started");
+    rejects(5, "47|53\n", 0, "there are no updates in the file");
+    rejects(6, "....\n.#..\n", 0, "there is no guard in the map");
+    rejects(6, "..^.\n.#v.\n", 2, "one of 2 guards found in the map, expected exactly one");
+    rejects(8, "..a.\n..#.\n", 2, "unexpected `#` at column 3");
+    rejects(9, "2333a\n", 1, "`a` at column 5 is not a digit");
+    rejects(10, "0123\n12a4\n", 2, "unexpected `a` at column 3");
+    rejects(12, "AAB\nAaB\n", 2, "unexpected `a` at column 2");
+    rejects(13, "Button A: X+94, Y+34\nButton B: X+22, Y+67\n", 2, "the last claw machine is incomplete");
+    rejects(13, "Button A: X+94, Y+34\nButton B: X+22, Y+67\nPrize: X=8400 Y=5400\n", 3, "expected `Prize: X=8400, Y=5400`");
+    rejects(14, "p=0,4 v=3,-3\np=-1,4 v=3,-3\n", 2, "robot positions are non-negative integers");
+    rejects(14, "p=0,4 v=3,-3\np=0,4 v=3\n", 2, "expected `p=0,4 v=3,-3`");
+    rejects(1, "\n", 0, "file is empty");
+}
@@ -0,0 +1,108 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpStream,
+    process::{Child, Command, Stdio},
+};
+
+// Server runs `aoc serve` on a port picked by the system, and stops it when dropped
+struct Server {
+    child: Child,
+    address: String,
+}
+
+impl Server {
+    fn start() -> Self {
+        Self::start_with(&[])
+    }
+
+    fn start_with(args: &[&str]) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_aoc"))
+            .args(["serve", "--port", "0"])
+            .args(args)
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("Failed to start the server");
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().unwrap()).read_line(&mut line).unwrap();
+        let address = line.trim().strip_prefix("Listening on ").expect("No address announced").to_string();
+        Self { child, address }
+    }
+
+    fn request(&self, method: &str, path: &str, body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(&self.address).unwrap();
+        write!(stream, "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}", method, path, body.len(), body).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response.split_whitespace().nth(1).unwrap().parse().unwrap();
+        let body = response.split_once("\r\n\r\n").unwrap().1.to_string();
+        (status, body)
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+const DAY_1_EXAMPLE: &str = "3   4\n4   3\n2   5\n1   3\n3   9\n3   3\n";
+
+#[test]
+fn solves_both_parts_of_a_day() {
+    let server = Server::start();
+    let (status, body) = server.request("POST", "/days/1/parts/1", DAY_1_EXAMPLE);
+    assert_eq!(status, 200, "{}", body);
+    assert!(body.contains("\"answer\":\"11\""), "{}", body);
+    assert!(body.contains("\"wall_time_ms\":"), "{}", body);
+    let (status, body) = server.request("POST", "/days/1/parts/2", DAY_1_EXAMPLE);
+    assert_eq!(status, 200, "{}", body);
+    assert!(body.contains("\"answer\":\"31\""), "{}", body);
+}
+
+#[test]
+fn rejects_unknown_routes_and_methods() {
+    let server = Server::start();
+    assert_eq!(server.request("POST", "/days/1/parts/3", DAY_1_EXAMPLE).0, 404);
+    assert_eq!(server.request("POST", "/days/one/parts/1", DAY_1_EXAMPLE).0, 404);
+    assert_eq!(server.request("POST", "/solve", DAY_1_EXAMPLE).0, 404);
+    assert_eq!(server.request("GET", "/days/1/parts/1", "").0, 405);
+}
+
+#[test]
+fn reports_solver_failures() {
+    let server = Server::start();
+    let (status, body) = server.request("POST", "/days/1/parts/1", "3 not-a-number\n");
+    assert_eq!(status, 422, "{}", body);
+    assert!(body.contains("\"error\":"), "{}", body);
+}
+
+#[test]
+fn rejects_oversized_inputs() {
+    let server = Server::start();
+    let mut stream = TcpStream::connect(&server.address).unwrap();
+    write!(stream, "POST /days/1/parts/1 HTTP/1.1\r\nHost: localhost\r\nContent-Length: 999999999999999\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 413"), "{}", response);
+    // the server is still up
+    assert_eq!(server.request("POST", "/days/1/parts/1", DAY_1_EXAMPLE).0, 200);
+}
+
+#[test]
+fn stops_solvers_running_too_long() {
+    let server = Server::start_with(&["--solve-timeout", "0.5"]);
+    // compacting a disk of 40000 blocks one file at a time takes seconds
+    let mut state: u64 = 1;
+    let disk: String = (0..40000)
+        .map(|_| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            char::from(b'1' + (state >> 33) as u8 % 9)
+        })
+        .collect();
+    let (status, body) = server.request("POST", "/days/9/parts/2", &disk);
+    assert_eq!(status, 422, "{}", body);
+    assert!(body.contains("was stopped after 0.5s"), "{}", body);
+    // the worker is free again
+    assert_eq!(server.request("POST", "/days/1/parts/1", DAY_1_EXAMPLE).0, 200);
+}
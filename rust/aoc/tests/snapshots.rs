@@ -0,0 +1,12 @@
+use std::process::Command;
+
+// the text renderings of the days must match the snapshots checked in under snapshots/,
+// run `aoc snapshot` to see what changed and `aoc snapshot --accept` to keep the new output
+#[test]
+fn renderings_match_the_snapshots() {
+    let output = Command::new(env!("CARGO_BIN_EXE_aoc"))
+        .arg("snapshot")
+        .output()
+        .expect("Failed to run aoc snapshot");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+}
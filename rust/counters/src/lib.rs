@@ -0,0 +1,57 @@
+use serde::{ser::SerializeMap, Serialize, Serializer};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Counter counts the calls of a solver step, it is a static so any function can bump it,
+// and it is reported with --stats and in --dump-state
+pub struct Counter {
+    name: &'static str,
+    label: &'static str,
+    count: AtomicU64,
+}
+
+impl Counter {
+    // name is the key in the state dump, label the line printed with --stats
+    pub const fn new(name: &'static str, label: &'static str) -> Self {
+        Self { name, label, count: AtomicU64::new(0) }
+    }
+
+    pub fn add(&self) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+}
+
+// Stats is the value of every counter of a day at one point, dumped as a map from name to count
+pub struct Stats {
+    values: Vec<(&'static str, &'static str, u64)>,
+}
+
+impl Stats {
+    pub fn collect(counters: &[&Counter]) -> Self {
+        Self { values: counters.iter().map(|counter| (counter.name, counter.label, counter.get())).collect() }
+    }
+
+    pub fn print(&self) {
+        for (_, label, count) in &self.values {
+            println!("{}: {}", label, count);
+        }
+    }
+}
+
+impl Serialize for Stats {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.values.len()))?;
+        for (name, _, count) in &self.values {
+            map.serialize_entry(name, count)?;
+        }
+        map.end()
+    }
+}
+
+// requested tells if the counters should be printed, with --stats
+pub fn requested() -> bool {
+    std::env::args().any(|arg| arg == "--stats")
+}
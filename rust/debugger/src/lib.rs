@@ -0,0 +1,175 @@
+use std::fmt::Display;
+
+// a snapshot is kept every CHECKPOINT steps, going back restores the closest one before the target
+// and replays the steps in between, so memory stays bounded however long the simulation runs
+const CHECKPOINT: usize = 64;
+
+// Machine is a simulation that moves forward one step at a time and can be saved and restored
+pub trait Machine {
+    type Snapshot;
+    type Event: Display;
+
+    // CONDITIONS lists the breakpoint conditions understood by condition, for the help
+    const CONDITIONS: &'static str;
+
+    // step moves the simulation one step forward and tells what happened, None when it is over
+    fn step(&mut self) -> Option<Self::Event>;
+    fn snapshot(&self) -> Self::Snapshot;
+    fn restore(&mut self, snapshot: &Self::Snapshot);
+    // show prints the current state
+    fn show(&self);
+    // condition parses the words after `break` into a condition checked after every step
+    fn condition(words: &[&str]) -> Option<Condition<Self>>;
+}
+
+pub type Condition<M> = Box<dyn Fn(&M, &<M as Machine>::Event) -> bool>;
+
+enum Breakpoint<M: Machine> {
+    Step(usize),
+    When(String, Condition<M>),
+}
+
+impl<M: Machine> Breakpoint<M> {
+    fn hit(&self, machine: &M, event: &M::Event, step: usize) -> bool {
+        match self {
+            Breakpoint::Step(at) => step == *at,
+            Breakpoint::When(_, condition) => condition(machine, event),
+        }
+    }
+
+    fn description(&self) -> String {
+        match self {
+            Breakpoint::Step(at) => format!("step {}", at),
+            Breakpoint::When(description, _) => description.clone(),
+        }
+    }
+}
+
+pub struct Debugger<M: Machine> {
+    machine: M,
+    step: usize,
+    // checkpoints[k] is the state at step k * CHECKPOINT
+    checkpoints: Vec<M::Snapshot>,
+    // end is the number of steps of the simulation, once it has been reached
+    end: Option<usize>,
+    breakpoints: Vec<Breakpoint<M>>,
+}
+
+impl<M: Machine> Debugger<M> {
+    pub fn new(machine: M) -> Self {
+        let checkpoints = vec![machine.snapshot()];
+        Self {
+            machine,
+            step: 0,
+            checkpoints,
+            end: None,
+            breakpoints: Vec::new(),
+        }
+    }
+
+    // forward runs up to n steps, stopping early at a breakpoint or at the end of the simulation
+    fn forward(&mut self, n: usize) {
+        let mut last = None;
+        for _ in 0..n {
+            if self.end == Some(self.step) {
+                break;
+            }
+            let Some(event) = self.machine.step() else {
+                self.end = Some(self.step);
+                break;
+            };
+            self.step += 1;
+            if self.checkpoints.len() * CHECKPOINT == self.step {
+                self.checkpoints.push(self.machine.snapshot());
+            }
+            let hit = self.breakpoints.iter().position(|breakpoint| breakpoint.hit(&self.machine, &event, self.step));
+            last = Some(event);
+            if let Some(idx) = hit {
+                println!("step {}: {}", self.step, last.unwrap());
+                println!("Breakpoint {} hit: {}", idx + 1, self.breakpoints[idx].description());
+                return;
+            }
+        }
+        if let Some(event) = last {
+            println!("step {}: {}", self.step, event);
+        }
+        if self.end == Some(self.step) {
+            println!("The simulation is over after {} steps", self.step);
+        }
+    }
+
+    // back goes n steps back, without stopping at breakpoints
+    fn back(&mut self, n: usize) {
+        let target = self.step.saturating_sub(n);
+        self.machine.restore(&self.checkpoints[target / CHECKPOINT]);
+        self.step = target / CHECKPOINT * CHECKPOINT;
+        while self.step < target {
+            self.machine.step();
+            self.step += 1;
+        }
+        println!("step {}", self.step);
+    }
+
+    // run reads debugger commands until quit or end of input
+    pub fn run(&mut self) {
+        let help = format!(
+            "Commands:
+  step [n]           move n steps forward (1 by default), stopping at breakpoints
+  back [n]           move n steps back (1 by default)
+  continue           move forward until a breakpoint is hit or the simulation is over
+  break step <n>     stop when step n is reached
+  break <condition>  stop after a step matching the condition, one of:
+{}
+  breaks             list the breakpoints
+  delete <n>         remove breakpoint n
+  show               print the current state
+  quit",
+            M::CONDITIONS
+        );
+        println!("Debugging from step 0, type help for commands");
+        let count = |n: Option<&&str>| match n {
+            None => Some(1),
+            Some(n) => n.parse::<usize>().ok(),
+        };
+        repl::run(self, &help, |debugger| format!("[step {}] > ", debugger.step), |debugger, words| {
+            match words {
+                ["step", rest @ ..] | ["s", rest @ ..] if rest.len() <= 1 => match count(rest.first()) {
+                    Some(n) => debugger.forward(n),
+                    None => println!("Invalid number of steps"),
+                },
+                ["back", rest @ ..] | ["b", rest @ ..] if rest.len() <= 1 => match count(rest.first()) {
+                    Some(n) => debugger.back(n),
+                    None => println!("Invalid number of steps"),
+                },
+                ["continue"] | ["c"] => debugger.forward(usize::MAX),
+                ["break", "step", n] => match n.parse::<usize>() {
+                    Ok(n) => debugger.add(Breakpoint::Step(n)),
+                    Err(_) => println!("Invalid step"),
+                },
+                ["break", condition @ ..] => match M::condition(condition) {
+                    Some(check) => debugger.add(Breakpoint::When(condition.join(" "), check)),
+                    None => println!("Unknown condition, type help for the conditions"),
+                },
+                ["breaks"] => {
+                    for (idx, breakpoint) in debugger.breakpoints.iter().enumerate() {
+                        println!("{}: {}", idx + 1, breakpoint.description());
+                    }
+                }
+                ["delete", n] => match n.parse::<usize>() {
+                    Ok(n) if n >= 1 && n <= debugger.breakpoints.len() => {
+                        debugger.breakpoints.remove(n - 1);
+                    }
+                    _ => println!("No such breakpoint"),
+                },
+                ["show"] => debugger.machine.show(),
+                _ => return false,
+            }
+            true
+        });
+    }
+
+    fn add(&mut self, breakpoint: Breakpoint<M>) {
+        println!("Breakpoint {}: {}", self.breakpoints.len() + 1, breakpoint.description());
+        self.breakpoints.push(breakpoint);
+    }
+}
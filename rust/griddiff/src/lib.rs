@@ -0,0 +1,161 @@
+use std::{collections::BTreeMap, fmt};
+
+use render::{ansi, Rgb};
+
+// Change is a cell that differs between the two grids, None when the cell is missing from a grid
+// because its row is shorter or the grid has fewer rows
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Change {
+    pub row: usize,
+    pub col: usize,
+    pub left: Option<char>,
+    pub right: Option<char>,
+}
+
+pub struct GridDiff {
+    right: Vec<Vec<char>>,
+    changes: Vec<Change>,
+}
+
+// diff compares two text grids cell by cell, row by row
+pub fn diff(left: &str, right: &str) -> GridDiff {
+    let left: Vec<Vec<char>> = left.lines().map(|line| line.chars().collect()).collect();
+    let right: Vec<Vec<char>> = right.lines().map(|line| line.chars().collect()).collect();
+    let mut changes = Vec::new();
+    for row in 0..left.len().max(right.len()) {
+        let (left_row, right_row) = (left.get(row), right.get(row));
+        let width = left_row.map_or(0, |cells| cells.len()).max(right_row.map_or(0, |cells| cells.len()));
+        for col in 0..width {
+            let left = left_row.and_then(|cells| cells.get(col)).copied();
+            let right = right_row.and_then(|cells| cells.get(col)).copied();
+            if left != right {
+                changes.push(Change { row, col, left, right });
+            }
+        }
+    }
+    GridDiff { right, changes }
+}
+
+// assert_same panics with the highlighted differences when the grids are not the same, for tests
+#[track_caller]
+pub fn assert_same(left: &str, right: &str) {
+    let diff = diff(left, right);
+    if !diff.is_same() {
+        panic!("grids differ\n{}", diff);
+    }
+}
+
+impl GridDiff {
+    pub fn is_same(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    // counts groups the changes by category, what the cell was in the left grid and what it is in the right one
+    pub fn counts(&self) -> BTreeMap<(Option<char>, Option<char>), usize> {
+        let mut counts = BTreeMap::new();
+        for change in self.changes.iter() {
+            *counts.entry((change.left, change.right)).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    // render draws the right grid with the differing cells highlighted, on a red background when colour
+    // is on and as * otherwise, cells only found in the left grid are drawn too so every change is visible
+    pub fn render(&self, colour: bool) -> String {
+        let height = self.right.len().max(self.changes.last().map_or(0, |change| change.row + 1));
+        let mut grid: Vec<Vec<Option<char>>> = (0..height)
+            .map(|row| self.right.get(row).map_or(Vec::new(), |cells| cells.iter().map(|c| Some(*c)).collect()))
+            .collect();
+        let mut changed = vec![Vec::new(); height];
+        for change in self.changes.iter() {
+            let cells = &mut grid[change.row];
+            if cells.len() <= change.col {
+                cells.resize(change.col + 1, None);
+            }
+            changed[change.row].push(change.col);
+        }
+        let mut out = String::new();
+        for (row, cells) in grid.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                let c = cell.unwrap_or(' ');
+                if !changed[row].contains(&col) {
+                    out.push(c);
+                } else if colour {
+                    out.push_str(&ansi::bg(c, Rgb::RED));
+                } else {
+                    out.push('*');
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    // report lists the counts per category and every change, followed by the rendering
+    pub fn report(&self, colour: bool) -> String {
+        if self.is_same() {
+            return "The grids are the same\n".to_string();
+        }
+        let mut out = format!("{} cells differ\n", self.changes.len());
+        for ((left, right), count) in self.counts() {
+            out.push_str(&format!("  {} -> {}: {}\n", cell(left), cell(right), count));
+        }
+        for change in self.changes.iter() {
+            out.push_str(&format!("  ({}, {}): {} -> {}\n", change.row, change.col, cell(change.left), cell(change.right)));
+        }
+        out.push_str(&self.render(colour));
+        out
+    }
+}
+
+fn cell(c: Option<char>) -> String {
+    match c {
+        Some(c) => format!("'{}'", c),
+        None => "nothing".to_string(),
+    }
+}
+
+impl fmt::Display for GridDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.report(false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEFT: &str = "....#\n.XX..\n#....\n";
+    const RIGHT: &str = "....#\n.X.#.\n#....\n";
+
+    #[test]
+    fn counts_the_changed_cells_by_category() {
+        let diff = diff(LEFT, RIGHT);
+        assert!(!diff.is_same());
+        assert_eq!(diff.changes().len(), 2);
+        assert_eq!(diff.counts().get(&(Some('X'), Some('.'))), Some(&1));
+        assert_eq!(diff.counts().get(&(Some('.'), Some('#'))), Some(&1));
+        assert_eq!(diff.render(false), "....#\n.X**.\n#....\n");
+        assert_same(LEFT, LEFT);
+    }
+
+    #[test]
+    fn reports_cells_missing_from_either_grid() {
+        let diff = diff("ab\ncd\n", "a\ncd\nef\n");
+        assert_eq!(diff.changes(), [
+            Change { row: 0, col: 1, left: Some('b'), right: None },
+            Change { row: 2, col: 0, left: None, right: Some('e') },
+            Change { row: 2, col: 1, left: None, right: Some('f') },
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "grids differ")]
+    fn assert_same_panics_on_a_difference() {
+        assert_same(LEFT, RIGHT);
+    }
+}
@@ -0,0 +1,50 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+
+use memmap2::Mmap;
+
+// Lines streams the lines of a file without reading it whole, only the current line is held in memory
+pub struct Lines(io::Lines<BufReader<File>>);
+
+pub fn lines(path: impl AsRef<Path>) -> Lines {
+    let file = File::open(path).expect("Failed to read file");
+    Lines(BufReader::new(file).lines())
+}
+
+impl Iterator for Lines {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.0.next().map(|line| line.expect("Failed to read file"))
+    }
+}
+
+// Grid is a grid file mapped in memory, rows are slices of the mapping so the file is never copied
+// as a whole and the operating system pages it in as the rows are read
+pub struct Grid {
+    map: Option<Mmap>,
+}
+
+impl Grid {
+    pub fn open(path: impl AsRef<Path>) -> Self {
+        let file = File::open(path).expect("Failed to read file");
+        // an empty file can't be mapped, it is a grid without rows
+        let empty = file.metadata().expect("Failed to read file").len() == 0;
+        // SAFETY: the input files are not modified while a day is solving them
+        let map = (!empty).then(|| unsafe { Mmap::map(&file) }.expect("Failed to map file"));
+        Self { map }
+    }
+
+    // rows returns every line of the grid without its line ending
+    pub fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        let bytes: &[u8] = self.map.as_deref().unwrap_or_default();
+        let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+        bytes
+            .split(|byte| *byte == b'\n')
+            .filter(move |_| !bytes.is_empty())
+            .map(|row| row.strip_suffix(b"\r").unwrap_or(row))
+    }
+}
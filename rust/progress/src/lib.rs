@@ -0,0 +1,105 @@
+use std::{
+    io::{stderr, IsTerminal, Write},
+    time::{Duration, Instant},
+};
+
+// the progress line is redrawn at most this often
+const REDRAW: Duration = Duration::from_millis(100);
+
+// TIMED_OUT is the exit code of a solver stopped by --timeout, the same one timeout(1) uses
+pub const TIMED_OUT: i32 = 124;
+
+// Progress counts the steps of a long running loop, shows the percentage done and an ETA on stderr
+// when it is a terminal, and tells the loop to stop once the time limit is over
+pub struct Progress {
+    label: String,
+    total: u64,
+    done: u64,
+    started: Instant,
+    drawn: Option<Instant>,
+    deadline: Option<Instant>,
+    visible: bool,
+}
+
+impl Progress {
+    pub fn new(label: &str, total: u64) -> Self {
+        let started = Instant::now();
+        Self {
+            label: label.to_string(),
+            total,
+            done: 0,
+            started,
+            drawn: None,
+            deadline: None,
+            visible: stderr().is_terminal(),
+        }
+    }
+
+    // timeout sets the time limit, counted from the creation of the progress
+    pub fn timeout(mut self, limit: Option<Duration>) -> Self {
+        self.deadline = limit.map(|limit| self.started + limit);
+        self
+    }
+
+    // step records one more step done, it returns false when the time limit is over
+    // and the loop should stop with what it has so far
+    pub fn step(&mut self) -> bool {
+        let now = Instant::now();
+        if self.deadline.is_some_and(|deadline| now >= deadline) {
+            self.clear();
+            return false;
+        }
+        self.done += 1;
+        if self.visible && self.drawn.is_none_or(|drawn| now - drawn >= REDRAW) {
+            self.draw();
+            self.drawn = Some(now);
+        }
+        true
+    }
+
+    pub fn done(&self) -> u64 {
+        self.done
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    // finish removes the progress line so it does not mix with the output that follows
+    pub fn finish(&self) {
+        self.clear();
+    }
+
+    fn draw(&self) {
+        let fraction = if self.total == 0 { 1.0 } else { self.done as f64 / self.total as f64 };
+        let elapsed = self.elapsed().as_secs_f64();
+        let eta = if self.done == 0 { 0.0 } else { elapsed / fraction - elapsed };
+        eprint!("\r\x1b[2K{}: {:5.1}% ({}/{}) ETA {:.1}s", self.label, fraction * 100.0, self.done, self.total, eta);
+        stderr().flush().ok();
+    }
+
+    fn clear(&self) {
+        if self.visible && self.drawn.is_some() {
+            eprint!("\r\x1b[2K");
+            stderr().flush().ok();
+        }
+    }
+}
+
+// timeout reads the `--timeout <seconds>` option, fractions of a second are allowed
+pub fn timeout() -> Option<Duration> {
+    std::env::args().skip_while(|arg| arg != "--timeout").nth(1).map(|seconds| {
+        match seconds.parse::<f64>() {
+            // from_secs_f64 panics on negative, NaN and infinite durations
+            Ok(limit) if limit.is_finite() && limit >= 0.0 => Duration::from_secs_f64(limit),
+            _ => {
+                eprintln!("Invalid --timeout `{}`, expected a number of seconds", seconds);
+                std::process::exit(2);
+            }
+        }
+    })
+}
@@ -0,0 +1,23 @@
+use std::{fmt::Display, io::{stdout, IsTerminal}};
+
+use crate::Rgb;
+
+// enabled tells if colour was requested and stdout is a terminal,
+// colours are never written to pipes or files
+pub fn enabled(requested: bool) -> bool {
+    requested && stdout().is_terminal()
+}
+
+// fg paints the text with the given foreground colour
+pub fn fg(text: impl Display, colour: Rgb) -> String {
+    format!("\x1b[38;2;{};{};{}m{}\x1b[0m", colour.0, colour.1, colour.2, text)
+}
+
+// bg paints the text over the given background colour, using black or white ink to keep it readable
+pub fn bg(text: impl Display, colour: Rgb) -> String {
+    let ink = if colour.is_dark() { Rgb::WHITE } else { Rgb::BLACK };
+    format!(
+        "\x1b[48;2;{};{};{}m\x1b[38;2;{};{};{}m{}\x1b[0m",
+        colour.0, colour.1, colour.2, ink.0, ink.1, ink.2, text
+    )
+}
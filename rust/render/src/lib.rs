@@ -0,0 +1,222 @@
+pub mod ansi;
+
+use std::{fs::File, io::{self, BufWriter, Write}, path::Path};
+
+// size in pixels of a single grid cell in the rendered images
+const SVG_CELL: usize = 10;
+const PNG_CELL: usize = 6;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const WHITE: Rgb = Rgb(255, 255, 255);
+    pub const BLACK: Rgb = Rgb(0, 0, 0);
+    pub const GREY: Rgb = Rgb(160, 160, 160);
+    pub const LIGHT_GREY: Rgb = Rgb(230, 230, 230);
+    pub const RED: Rgb = Rgb(220, 50, 47);
+    pub const GREEN: Rgb = Rgb(60, 170, 80);
+    pub const BLUE: Rgb = Rgb(38, 139, 210);
+    pub const YELLOW: Rgb = Rgb(240, 200, 40);
+    pub const ORANGE: Rgb = Rgb(240, 130, 30);
+
+    fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+
+    fn is_dark(&self) -> bool {
+        // perceived luminance, dark backgrounds get white labels
+        299 * (self.0 as u32) + 587 * (self.1 as u32) + 114 * (self.2 as u32) < 128_000
+    }
+}
+
+// palette returns a distinct colour for the n-th category (a frequency, a region, ...)
+// hues are spread using the golden angle so neighbouring categories never look alike
+pub fn palette(n: usize) -> Rgb {
+    let hue = (n as f64 * 137.508) % 360.0;
+    hsv(hue, 0.55, 0.9)
+}
+
+// gradient blends linearly between two colours, t goes from 0.0 (from) to 1.0 (to)
+pub fn gradient(from: Rgb, to: Rgb, t: f64) -> Rgb {
+    let t = t.clamp(0.0, 1.0);
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    Rgb(mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+}
+
+fn hsv(hue: f64, saturation: f64, value: f64) -> Rgb {
+    let c = value * saturation;
+    let x = c * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let m = value - c;
+    let (r, g, b) = match hue as u32 / 60 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let channel = |v: f64| ((v + m) * 255.0).round() as u8;
+    Rgb(channel(r), channel(g), channel(b))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Edge {
+    North,
+    East,
+    South,
+    West,
+}
+
+// Canvas is a grid of coloured cells, with optional characters printed on top of them
+// and walls (fences) drawn along the edges of the cells
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    fills: Vec<Rgb>,
+    labels: Vec<Option<char>>,
+    walls: Vec<(usize, usize, Edge)>,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize, background: Rgb) -> Self {
+        Self {
+            width,
+            height,
+            fills: vec![background; width * height],
+            labels: vec![None; width * height],
+            walls: Vec::new(),
+        }
+    }
+
+    pub fn fill(&mut self, row: usize, col: usize, colour: Rgb) {
+        self.fills[row * self.width + col] = colour;
+    }
+
+    pub fn label(&mut self, row: usize, col: usize, c: char) {
+        self.labels[row * self.width + col] = Some(c);
+    }
+
+    pub fn wall(&mut self, row: usize, col: usize, edge: Edge) {
+        self.walls.push((row, col, edge));
+    }
+
+    // save writes the canvas as png if the path ends in .png, otherwise as svg
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let is_png = Path::new(path)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+        if is_png {
+            self.write_png(path)
+        } else {
+            self.write_svg(path)
+        }
+    }
+
+    pub fn write_svg(&self, path: &str) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(self.to_svg().as_bytes())?;
+        file.flush()
+    }
+
+    pub fn to_svg(&self) -> String {
+        let mut svg = String::new();
+        svg.push_str(&format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"monospace\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\">\n",
+            self.width * SVG_CELL,
+            self.height * SVG_CELL,
+            SVG_CELL - 2
+        ));
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let colour = self.fills[row * self.width + col];
+                let (x, y) = (col * SVG_CELL, row * SVG_CELL);
+                svg.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                    x, y, SVG_CELL, SVG_CELL, colour.hex()
+                ));
+                if let Some(c) = self.labels[row * self.width + col] {
+                    let ink = if colour.is_dark() { Rgb::WHITE } else { Rgb::BLACK };
+                    svg.push_str(&format!(
+                        "<text x=\"{}\" y=\"{}\" fill=\"{}\">{}</text>\n",
+                        x + SVG_CELL / 2,
+                        y + SVG_CELL / 2,
+                        ink.hex(),
+                        escape(c)
+                    ));
+                }
+            }
+        }
+        for (row, col, edge) in self.walls.iter() {
+            let ((x1, y1), (x2, y2)) = edge_line(*row, *col, *edge, SVG_CELL);
+            svg.push_str(&format!(
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\" stroke-width=\"1.5\"/>\n",
+                x1, y1, x2, y2
+            ));
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    // labels are not drawn in png images, there is no font to rasterise them with
+    pub fn write_png(&self, path: &str) -> io::Result<()> {
+        let pixel_width = self.width * PNG_CELL;
+        let pixel_height = self.height * PNG_CELL;
+        let mut pixels = vec![0u8; pixel_width * pixel_height * 3];
+        let mut paint = |x: usize, y: usize, colour: Rgb| {
+            let idx = (y * pixel_width + x) * 3;
+            pixels[idx] = colour.0;
+            pixels[idx + 1] = colour.1;
+            pixels[idx + 2] = colour.2;
+        };
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let colour = self.fills[row * self.width + col];
+                for y in 0..PNG_CELL {
+                    for x in 0..PNG_CELL {
+                        paint(col * PNG_CELL + x, row * PNG_CELL + y, colour);
+                    }
+                }
+            }
+        }
+        for (row, col, edge) in self.walls.iter() {
+            // walls are painted on the inner border of the cell they belong to
+            for k in 0..PNG_CELL {
+                let (x, y) = match edge {
+                    Edge::North => (k, 0),
+                    Edge::South => (k, PNG_CELL - 1),
+                    Edge::West => (0, k),
+                    Edge::East => (PNG_CELL - 1, k),
+                };
+                paint(col * PNG_CELL + x, row * PNG_CELL + y, Rgb::BLACK);
+            }
+        }
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, pixel_width as u32, pixel_height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        writer.write_image_data(&pixels).map_err(io::Error::other)?;
+        writer.finish().map_err(io::Error::other)
+    }
+}
+
+fn edge_line(row: usize, col: usize, edge: Edge, size: usize) -> ((usize, usize), (usize, usize)) {
+    let (left, top) = (col * size, row * size);
+    let (right, bottom) = (left + size, top + size);
+    match edge {
+        Edge::North => ((left, top), (right, top)),
+        Edge::East => ((right, top), (right, bottom)),
+        Edge::South => ((left, bottom), (right, bottom)),
+        Edge::West => ((left, top), (left, bottom)),
+    }
+}
+
+fn escape(c: char) -> String {
+    match c {
+        '<' => "&lt;".to_string(),
+        '>' => "&gt;".to_string(),
+        '&' => "&amp;".to_string(),
+        _ => c.to_string(),
+    }
+}
@@ -0,0 +1,26 @@
+use std::io::{stdin, stdout, Write};
+
+// run reads commands until quit, exit or the end of input. Each line is split into words and given to
+// handle with the state, which returns false for a command it doesn't know. Empty lines, help, quit and exit
+// are handled here, and prompt is printed before every line from the current state
+pub fn run<S>(state: &mut S, help: &str, prompt: impl Fn(&S) -> String, mut handle: impl FnMut(&mut S, &[&str]) -> bool) {
+    loop {
+        print!("{}", prompt(state));
+        stdout().flush().unwrap();
+        let mut line = String::new();
+        if stdin().read_line(&mut line).unwrap() == 0 {
+            break;
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] => continue,
+            ["help"] => println!("{}", help),
+            ["quit"] | ["exit"] => break,
+            words => {
+                if !handle(state, words) {
+                    println!("Unknown command, type help for commands");
+                }
+            }
+        }
+    }
+}
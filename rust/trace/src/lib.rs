@@ -0,0 +1,92 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Lines, Write},
+    marker::PhantomData,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+// A trace is a JSON Lines file, the first line tells the day and the state the simulation starts from,
+// every other line is one event, so any intermediate state is rebuilt by applying the events in order
+#[derive(Serialize, Deserialize)]
+struct Header<T> {
+    day: u32,
+    start: T,
+}
+
+pub struct Recorder {
+    out: BufWriter<File>,
+}
+
+impl Recorder {
+    pub fn create<T: Serialize>(path: &str, day: u32, start: &T) -> Self {
+        let mut recorder = Self { out: BufWriter::new(File::create(path).expect("Failed to create trace file")) };
+        recorder.record(&Header { day, start });
+        recorder
+    }
+
+    pub fn record<E: Serialize>(&mut self, event: &E) {
+        serde_json::to_writer(&mut self.out, event).expect("Failed to write trace");
+        self.out.write_all(b"\n").expect("Failed to write trace");
+    }
+
+    // flush writes out the buffered events, dropping the recorder does it too
+    // but exiting the process doesn't drop anything
+    pub fn flush(&mut self) {
+        self.out.flush().expect("Failed to write trace");
+    }
+}
+
+// recorder creates the recorder asked for with `--record <file>`
+pub fn recorder<T: Serialize>(day: u32, start: &T) -> Option<Recorder> {
+    std::env::args()
+        .skip_while(|arg| arg != "--record")
+        .nth(1)
+        .map(|path| Recorder::create(&path, day, start))
+}
+
+// Trace reads the events of a trace back, one line at a time
+pub struct Trace<E> {
+    lines: Lines<BufReader<File>>,
+    line: usize,
+    events: PhantomData<E>,
+}
+
+// open reads the header of the trace, which must have been recorded by the given day
+pub fn open<T: DeserializeOwned, E: DeserializeOwned>(path: &str, day: u32) -> (T, Trace<E>) {
+    let mut lines = BufReader::new(File::open(path).expect("Failed to open trace file")).lines();
+    let first = lines.next().expect("Empty trace").expect("Failed to read trace");
+    let header: Header<T> = serde_json::from_str(&first).expect("Invalid trace header");
+    if header.day != day {
+        panic!("The trace was recorded by day {}, not day {}", header.day, day);
+    }
+    (header.start, Trace { lines, line: 1, events: PhantomData })
+}
+
+impl<E: DeserializeOwned> Iterator for Trace<E> {
+    type Item = E;
+
+    fn next(&mut self) -> Option<E> {
+        let line = self.lines.next()?.expect("Failed to read trace");
+        self.line += 1;
+        Some(serde_json::from_str(&line).unwrap_or_else(|e| panic!("Invalid event on line {}: {}", self.line, e)))
+    }
+}
+
+// replay returns the trace given with `--replay <file>`
+pub fn replay() -> Option<String> {
+    std::env::args().skip_while(|arg| arg != "--replay").nth(1)
+}
+
+// at is the number of events to replay, `--at <n>`, every event when missing
+pub fn at() -> usize {
+    match std::env::args().skip_while(|arg| arg != "--at").nth(1) {
+        Some(n) => n.parse().expect("Invalid --at, expected a number of events"),
+        None => usize::MAX,
+    }
+}
+
+// check tells if the replayed events must also be compared with a fresh run of the simulation, `--check`
+pub fn check() -> bool {
+    std::env::args().any(|arg| arg == "--check")
+}